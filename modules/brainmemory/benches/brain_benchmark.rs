@@ -0,0 +1,32 @@
+// SimplyKI BrainMemory - Benchmarks
+// Erstellt: 2026-10-19 21:40:00 CEST
+
+use brainmemory::BrainMemory;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn filled(entries: usize) -> BrainMemory {
+    let mut brain = BrainMemory::new();
+    for i in 0..entries {
+        brain
+            .store(&format!("key_{}", i), serde_json::json!({"id": i, "data": format!("test data {}", i)}))
+            .expect("benchmark keys have no schema");
+    }
+    brain
+}
+
+fn bench_store(c: &mut Criterion) {
+    c.bench_function("store 1000", |b| b.iter(|| filled(black_box(1000))));
+}
+
+fn bench_retrieve(c: &mut Criterion) {
+    let mut brain = filled(1000);
+    c.bench_function("retrieve", |b| b.iter(|| brain.retrieve(black_box("key_500"))));
+}
+
+fn bench_search(c: &mut Criterion) {
+    let brain = filled(1000);
+    c.bench_function("search", |b| b.iter(|| brain.search(black_box("test data 500"), 10)));
+}
+
+criterion_group!(benches, bench_store, bench_retrieve, bench_search);
+criterion_main!(benches);
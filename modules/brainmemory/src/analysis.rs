@@ -0,0 +1,495 @@
+// SimplyKI BrainMemory - Text Analysis (Deutsch / English)
+// Erstellt: 2026-10-19 19:05:00 CEST
+
+use std::collections::{BTreeMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::text::tokenize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    /// Guessed per entry from its stopwords, German if undecided
+    Auto,
+    German,
+    English,
+    /// Normalization and case folding only
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyzerConfig {
+    pub language: Language,
+    /// Language per key namespace (`notes` covers `notes/...`); the
+    /// longest matching namespace wins
+    pub namespaces: BTreeMap<String, Language>,
+    pub stopwords: bool,
+    pub stemming: bool,
+    /// Also index the parts of German compounds
+    pub decompound: bool,
+    /// Words compounds are split into, on top of the built-in list
+    pub compound_words: Vec<String>,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            language: Language::Auto,
+            namespaces: BTreeMap::new(),
+            stopwords: true,
+            stemming: true,
+            decompound: true,
+            compound_words: Vec::new(),
+        }
+    }
+}
+
+/// Turns text into index terms. Each token is indexed as written
+/// (normalized), as its stem and, for German compounds, as its parts.
+/// Query tokens stand for their surface form and the stems of every
+/// configured language, so a query does not need to know the language of
+/// what it searches.
+#[derive(Debug, Clone)]
+pub struct Analyzer {
+    config: AnalyzerConfig,
+    compound_words: HashSet<String>,
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self::new(AnalyzerConfig::default())
+    }
+}
+
+impl Analyzer {
+    pub fn new(config: AnalyzerConfig) -> Self {
+        let compound_words = COMPOUND_WORDS
+            .iter()
+            .map(|w| normalize(w))
+            .chain(config.compound_words.iter().map(|w| normalize(w)))
+            .collect();
+        Self { config, compound_words }
+    }
+
+    pub fn config(&self) -> &AnalyzerConfig {
+        &self.config
+    }
+
+    /// Configured language of a key, before `Auto` is resolved
+    pub fn language_for(&self, key: &str) -> Language {
+        self.config
+            .namespaces
+            .iter()
+            .filter(|(ns, _)| key.strip_prefix(ns.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('/')))
+            .max_by_key(|(ns, _)| ns.len())
+            .map_or(self.config.language, |(_, language)| *language)
+    }
+
+    /// Terms of a document, one per occurrence, each flagged whether it
+    /// is a token as written (as opposed to a stem or compound part)
+    pub fn index_terms(&self, key: &str, strings: &[&str]) -> Vec<(String, bool)> {
+        let tokens: Vec<String> = strings.iter().flat_map(|s| tokenize(&normalize(s))).collect();
+        let language = match self.language_for(key) {
+            Language::Auto => detect(&tokens),
+            language => language,
+        };
+        tokens
+            .iter()
+            .flat_map(|t| {
+                self.token_terms(t, language)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, term)| (term, i == 0))
+            })
+            .collect()
+    }
+
+    /// Index terms of a single normalized token, the token itself first
+    pub fn token_terms(&self, token: &str, language: Language) -> Vec<String> {
+        if self.config.stopwords && is_stopword(token, language) {
+            return Vec::new();
+        }
+
+        let mut terms = vec![token.to_string()];
+        if self.config.decompound && language == Language::German {
+            terms.extend(self.decompound(token));
+        }
+        if self.config.stemming {
+            let stems: Vec<String> = terms.iter().filter_map(|t| stem(t, language)).collect();
+            terms.extend(stems);
+        }
+        let mut distinct: Vec<String> = Vec::with_capacity(terms.len());
+        for term in terms {
+            if !distinct.contains(&term) {
+                distinct.push(term);
+            }
+        }
+        distinct
+    }
+
+    /// Whether a token of some text produces any of `terms`, whatever the
+    /// text's language; used to highlight matches
+    pub fn token_matches(&self, token: &str, terms: &[String]) -> bool {
+        let token = normalize(token);
+        [Language::German, Language::English]
+            .into_iter()
+            .any(|language| self.token_terms(&token, language).iter().any(|t| terms.contains(t)))
+    }
+
+    /// Per query token, the index terms it may match
+    pub fn query_groups(&self, text: &str) -> Vec<Vec<String>> {
+        let mut tokens = tokenize(&normalize(text));
+        tokens.dedup();
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        for token in tokens {
+            let group = self.query_variants(&token);
+            if !group.is_empty() && !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    /// Languages documents may be indexed in, `Auto` resolved to both
+    fn languages(&self) -> Vec<Language> {
+        let mut languages = Vec::new();
+        for language in std::iter::once(self.config.language).chain(self.config.namespaces.values().copied()) {
+            let resolved = match language {
+                Language::Auto => vec![Language::German, Language::English],
+                language => vec![language],
+            };
+            for language in resolved {
+                if !languages.contains(&language) {
+                    languages.push(language);
+                }
+            }
+        }
+        languages
+    }
+
+    /// Surface form and stems of one query token; empty for stopwords of
+    /// a configured language
+    pub fn query_variants(&self, token: &str) -> Vec<String> {
+        let token = normalize(token);
+        let languages = self.languages();
+        if self.config.stopwords && languages.iter().any(|&language| is_stopword(&token, language)) {
+            return Vec::new();
+        }
+
+        let mut variants = vec![token.clone()];
+        if self.config.stemming {
+            variants.extend(languages.iter().filter_map(|&language| stem(&token, language)));
+        }
+        variants.sort();
+        variants.dedup();
+        variants
+    }
+
+    /// Known words a German compound ends or starts with. The head (last
+    /// part) is found even if the rest of the word is unknown.
+    fn decompound(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < MIN_COMPOUND_LEN {
+            return Vec::new();
+        }
+
+        let mut parts = Vec::new();
+        for split in MIN_PART_LEN..=chars.len() - MIN_PART_LEN {
+            let head: String = chars[split..].iter().collect();
+            if !self.compound_words.contains(&head) {
+                continue;
+            }
+            // Longest known head; the modifier may carry a linking element
+            let modifier: String = chars[..split].iter().collect();
+            parts.push(head);
+            for linking in ["", "s", "es", "n", "en", "e"] {
+                if let Some(stripped) = modifier.strip_suffix(linking) {
+                    if self.compound_words.contains(stripped) {
+                        parts.push(stripped.to_string());
+                        break;
+                    }
+                }
+            }
+            break;
+        }
+        parts
+    }
+}
+
+const MIN_COMPOUND_LEN: usize = 7;
+const MIN_PART_LEN: usize = 3;
+
+/// Lowercase, fold `ß` to `ss` and compose a base letter with one directly
+/// following combining accent.
+///
+/// This is a limited German/Latin-1 fold, not Unicode NFC: only the accents
+/// in `compose` on Latin-1 vowels, `n` and `c` are composed, and a second
+/// combining mark after a composed letter is kept as is.
+pub fn normalize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let composed = chars.peek().and_then(|&mark| compose(c, mark));
+        match composed {
+            Some(composed) => {
+                chars.next();
+                out.extend(composed.to_lowercase());
+            }
+            None => out.extend(c.to_lowercase()),
+        }
+    }
+    out.replace('ß', "ss")
+}
+
+fn compose(base: char, mark: char) -> Option<char> {
+    let (bases, composed) = match mark {
+        '\u{0308}' => ("aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+        '\u{0301}' => ("aeiouyAEIOU", "áéíóúýÁÉÍÓÚ"),
+        '\u{0300}' => ("aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        '\u{0302}' => ("aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        '\u{0303}' => ("anoANO", "ãñõÃÑÕ"),
+        '\u{0327}' => ("cC", "çÇ"),
+        _ => return None,
+    };
+    bases.chars().position(|b| b == base).and_then(|i| composed.chars().nth(i))
+}
+
+/// German if it has more German than English stopwords, and when neither
+/// occurs
+fn detect(tokens: &[String]) -> Language {
+    let german = tokens.iter().filter(|t| GERMAN_STOPWORDS.contains(&t.as_str())).count();
+    let english = tokens.iter().filter(|t| ENGLISH_STOPWORDS.contains(&t.as_str())).count();
+    if english > german {
+        Language::English
+    } else {
+        Language::German
+    }
+}
+
+pub(crate) fn is_stopword(token: &str, language: Language) -> bool {
+    match language {
+        Language::German => GERMAN_STOPWORDS.contains(&token),
+        Language::English => ENGLISH_STOPWORDS.contains(&token),
+        Language::Auto | Language::None => false,
+    }
+}
+
+/// Stem if it differs from the token
+fn stem(token: &str, language: Language) -> Option<String> {
+    if token.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let stemmed = match language {
+        Language::German => stem_german(token),
+        Language::English => stem_english(token),
+        Language::Auto | Language::None => return None,
+    };
+    (stemmed != token).then_some(stemmed)
+}
+
+/// CISTEM (Weissweiler & Fraser, 2017), case-insensitive variant
+fn stem_german(word: &str) -> String {
+    let mut word = word.replace('ü', "u").replace('ö', "o").replace('ä', "a");
+    if word.starts_with("ge") && word.chars().count() >= 6 {
+        word = word[2..].to_string();
+    }
+    word = word.replace("sch", "$").replace("ei", "%").replace("ie", "&");
+
+    // Second letter of a double becomes `*`
+    let mut chars: Vec<char> = Vec::new();
+    for c in word.chars() {
+        if chars.last() == Some(&c) {
+            chars.push('*');
+        } else {
+            chars.push(c);
+        }
+    }
+
+    while chars.len() > 3 {
+        let len = chars.len();
+        let ends = |suffix: &str| chars.iter().rev().take(suffix.len()).rev().copied().eq(suffix.chars());
+        if len > 5 && (ends("em") || ends("er") || ends("nd")) {
+            chars.truncate(len - 2);
+        } else if ends("t") || ends("e") || ends("s") || ends("n") {
+            chars.truncate(len - 1);
+        } else {
+            break;
+        }
+    }
+
+    let mut out = String::new();
+    for c in chars {
+        match c {
+            '*' => {
+                if let Some(previous) = out.chars().last() {
+                    out.push(previous);
+                }
+            }
+            '$' => out.push_str("sch"),
+            '%' => out.push_str("ei"),
+            '&' => out.push_str("ie"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Light English stemmer: plurals, -ed/-ing (Porter step 1) and a few
+/// derivational suffixes
+fn stem_english(word: &str) -> String {
+    let mut w = word.to_string();
+    if w.chars().count() <= 3 {
+        return w;
+    }
+
+    if let Some(stem) = w.strip_suffix("sses") {
+        w = format!("{}ss", stem);
+    } else if let Some(stem) = w.strip_suffix("ies") {
+        w = format!("{}y", stem);
+    } else if w.ends_with('s') && !w.ends_with("ss") && !w.ends_with("us") && !w.ends_with("is") {
+        w.pop();
+    }
+
+    for suffix in ["ingly", "edly", "ing", "ed"] {
+        if w.ends_with("eed") {
+            break;
+        }
+        if let Some(stem) = w.strip_suffix(suffix) {
+            if stem.chars().count() >= 2 && has_vowel(stem) {
+                w = stem.to_string();
+                if w.ends_with("at") || w.ends_with("bl") || w.ends_with("iz") {
+                    w.push('e');
+                } else if ends_with_double_consonant(&w) {
+                    w.pop();
+                } else if is_short_cvc(&w) {
+                    w.push('e');
+                }
+            }
+            break;
+        }
+    }
+
+    for (suffix, replacement) in [
+        ("ational", "ate"),
+        ("tional", "tion"),
+        ("ization", "ize"),
+        ("iveness", "ive"),
+        ("fulness", "ful"),
+        ("ousness", "ous"),
+    ] {
+        if let Some(stem) = w.strip_suffix(suffix) {
+            if stem.chars().count() >= 3 {
+                w = format!("{}{}", stem, replacement);
+            }
+            break;
+        }
+    }
+    w
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+fn has_vowel(s: &str) -> bool {
+    s.chars().any(|c| is_vowel(c) || c == 'y')
+}
+
+fn ends_with_double_consonant(s: &str) -> bool {
+    let chars: Vec<char> = s.chars().collect();
+    let n = chars.len();
+    n >= 2 && chars[n - 1] == chars[n - 2] && !is_vowel(chars[n - 1]) && !matches!(chars[n - 1], 'l' | 's' | 'z')
+}
+
+/// Short consonant-vowel-consonant stem like `hop` or `stor`
+fn is_short_cvc(s: &str) -> bool {
+    let chars: Vec<char> = s.chars().collect();
+    let n = chars.len();
+    (3..=4).contains(&n)
+        && !is_vowel(chars[n - 3])
+        && is_vowel(chars[n - 2])
+        && !is_vowel(chars[n - 1])
+        && !matches!(chars[n - 1], 'w' | 'x' | 'y')
+}
+
+const GERMAN_STOPWORDS: &[&str] = &[
+    "aber", "alle", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "bist", "da", "dann", "das",
+    "dass", "dem", "den", "der", "des", "die", "dies", "diese", "dieser", "doch", "du", "durch", "ein", "eine",
+    "einem", "einen", "einer", "eines", "er", "es", "für", "hat", "hatte", "ich", "ihr", "im", "in", "ist",
+    "ja", "kann", "kein", "mit", "nach", "nicht", "noch", "nur", "ob", "oder", "sehr", "sich", "sie", "sind",
+    "so", "über", "um", "und", "uns", "unter", "vom", "von", "vor", "war", "was", "wenn", "wie", "wir",
+    "wird", "wo", "zu", "zum", "zur",
+];
+
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by",
+    "can", "could", "did", "do", "does", "for", "from", "had", "has", "have", "he", "her", "his", "how", "i",
+    "if", "in", "into", "is", "it", "its", "just", "more", "no", "not", "of", "on", "or", "our", "she", "so",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to", "was", "we",
+    "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+/// Common German compound parts, biased towards software and notes
+const COMPOUND_WORDS: &[&str] = &[
+    "abfrage", "adresse", "änderung", "anfrage", "antwort", "arbeit", "aufgabe", "ausgabe", "bank", "befehl",
+    "benutzer", "bereich", "bericht", "datei", "daten", "dienst", "eingabe", "eintrag", "ergebnis", "fehler",
+    "gedächtnis", "grenze", "größe", "index", "inhalt", "kontext", "konto", "liste", "meldung", "modul",
+    "name", "netz", "nutzer", "ordner", "passwort", "pfad", "plan", "projekt", "protokoll", "prozess",
+    "prüfung", "quelle", "rechner", "regel", "schlüssel", "schnittstelle", "sicherung", "sitzung", "speicher",
+    "sprache", "suche", "system", "test", "text", "verbindung", "verlauf", "version", "verwaltung",
+    "verzeichnis", "wert", "wissen", "zeit", "ziel", "zugriff", "zustand",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stemming() {
+        assert_eq!(stem_german("speicher"), "speich");
+        assert_eq!(stem_german("speichern"), "speich");
+        assert_eq!(stem_german("häuser"), stem_german("haus"));
+        assert_eq!(stem_english("running"), "run");
+        assert_eq!(stem_english("stored"), "store");
+        assert_eq!(stem_english("stores"), "store");
+        assert_eq!(stem_english("queries"), "query");
+        assert_eq!(normalize("Gro\u{0308}ße"), "grösse");
+    }
+
+    #[test]
+    fn test_normalize_is_a_limited_fold() {
+        assert_eq!(normalize("Cafe\u{0301} Ñ"), "café ñ");
+        // Outside the table: stacked marks and letters beyond Latin-1
+        assert_eq!(normalize("u\u{0308}\u{0301}"), "ü\u{0301}");
+        assert_eq!(normalize("e\u{0328}"), "e\u{0328}");
+        assert_eq!(normalize("s\u{030C}"), "s\u{030C}");
+    }
+
+    #[test]
+    fn test_compounds_and_languages() {
+        let mut config = AnalyzerConfig::default();
+        config.namespaces.insert("docs/en".to_string(), Language::English);
+        let analyzer = Analyzer::new(config);
+
+        let terms = analyzer.index_terms("notes/1", &["Der Arbeitsspeicher ist voll"]);
+        assert!(terms.contains(&("arbeitsspeicher".to_string(), true)));
+        assert!(terms.contains(&("speicher".to_string(), false)));
+        assert!(terms.contains(&("arbeit".to_string(), false)));
+        assert!(!terms.iter().any(|(t, _)| t == "der"));
+
+        assert_eq!(analyzer.language_for("docs/en/readme"), Language::English);
+        let terms = analyzer.index_terms("docs/en/readme", &["The stored queries"]);
+        assert!(terms.contains(&("query".to_string(), false)) && !terms.iter().any(|(t, _)| t == "the"));
+
+        let groups = analyzer.query_groups("die Speicher");
+        assert_eq!(groups, vec![vec!["speich".to_string(), "speicher".to_string()]]);
+
+        // Only the configured languages' stopwords are dropped from queries
+        let analyzer = Analyzer::new(AnalyzerConfig { language: Language::None, ..AnalyzerConfig::default() });
+        assert_eq!(analyzer.query_variants("die"), vec!["die".to_string()]);
+        let analyzer = Analyzer::new(AnalyzerConfig { language: Language::English, ..AnalyzerConfig::default() });
+        assert_eq!(analyzer.query_variants("bin"), vec!["bin".to_string()]);
+        assert!(analyzer.query_variants("will").is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+// SimplyKI BrainMemory - Approximate Nearest Neighbor Index (HNSW)
+// Erstellt: 2026-10-19 15:45:00 CEST
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::embedding::cosine;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HnswParams {
+    /// Links per node on the upper layers; layer 0 keeps twice as many
+    pub m: usize,
+    pub ef_construction: usize,
+    pub ef_search: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    key: String,
+    vector: Vec<f32>,
+    /// Neighbor ids per layer, layer 0 first
+    links: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+/// Cosine distance with a total order, for the heaps
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hierarchical navigable small world graph over normalized vectors.
+/// Removal leaves a tombstone that is still traversed but never returned;
+/// the graph is rebuilt once half of it is tombstones.
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    params: HnswParams,
+    nodes: Vec<Node>,
+    ids: HashMap<String, usize>,
+    entry: Option<usize>,
+    deleted: usize,
+    rng: u64,
+}
+
+impl HnswIndex {
+    pub fn new(params: HnswParams) -> Self {
+        Self {
+            params,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry: None,
+            deleted: 0,
+            rng: 0x2545f4914f6cdd1d,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn vector(&self, key: &str) -> Option<&[f32]> {
+        self.ids.get(key).map(|&id| self.nodes[id].vector.as_slice())
+    }
+
+    pub fn insert(&mut self, key: &str, vector: Vec<f32>) {
+        self.remove(key);
+
+        let level = self.random_level();
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            key: key.to_string(),
+            vector,
+            links: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.ids.insert(key.to_string(), id);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(id);
+            return;
+        };
+
+        let query = self.nodes[id].vector.clone();
+        let top = self.nodes[entry].links.len() - 1;
+        let mut nearest = vec![self.candidate(&query, entry)];
+
+        // Greedy descent through the layers above the new node
+        for layer in (level + 1..=top).rev() {
+            nearest = self.search_layer(&query, &nearest, 1, layer);
+        }
+
+        for layer in (0..=level.min(top)).rev() {
+            nearest = self.search_layer(&query, &nearest, self.params.ef_construction, layer);
+            let max_links = self.max_links(layer);
+            let neighbors: Vec<usize> = nearest.iter().take(max_links).map(|c| c.id).collect();
+            self.nodes[id].links[layer] = neighbors.clone();
+
+            for neighbor in neighbors {
+                self.nodes[neighbor].links[layer].push(id);
+                if self.nodes[neighbor].links[layer].len() > max_links {
+                    self.prune(neighbor, layer, max_links);
+                }
+            }
+        }
+
+        if level > top {
+            self.entry = Some(id);
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        let Some(id) = self.ids.remove(key) else {
+            return false;
+        };
+        self.nodes[id].deleted = true;
+        self.deleted += 1;
+        if self.deleted * 2 > self.nodes.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// The `k` keys closest to `query`, as (key, cosine similarity).
+    /// Asking for every key gives the exact ranking of all of them.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if k >= self.len() {
+            // The graph walk could miss nodes; a scan is exact and no slower
+            let mut all: Vec<(String, f32)> = self.nodes
+                .iter()
+                .filter(|node| !node.deleted)
+                .map(|node| (node.key.clone(), cosine(query, &node.vector)))
+                .collect();
+            all.sort_by(|a, b| b.1.total_cmp(&a.1));
+            return all;
+        }
+
+        let top = self.nodes[entry].links.len() - 1;
+        let mut nearest = vec![self.candidate(query, entry)];
+        for layer in (1..=top).rev() {
+            nearest = self.search_layer(query, &nearest, 1, layer);
+        }
+        // Tombstones take up result slots, so look a bit wider
+        let ef = self.params.ef_search.max(k) + self.deleted.min(k);
+        nearest = self.search_layer(query, &nearest, ef, 0);
+
+        nearest
+            .into_iter()
+            .filter(|c| !self.nodes[c.id].deleted)
+            .take(k)
+            .map(|c| (self.nodes[c.id].key.clone(), 1.0 - c.distance))
+            .collect()
+    }
+
+    fn candidate(&self, query: &[f32], id: usize) -> Candidate {
+        Candidate {
+            distance: 1.0 - cosine(query, &self.nodes[id].vector),
+            id,
+        }
+    }
+
+    /// Best-first search on one layer; returns up to `ef` nodes, closest first
+    fn search_layer(&self, query: &[f32], entry: &[Candidate], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry.iter().map(|c| c.id).collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = entry.iter().copied().map(Reverse).collect();
+        let mut results: BinaryHeap<Candidate> = entry.iter().copied().collect();
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            let worst = results.peek().map_or(f32::INFINITY, |c| c.distance);
+            if current.distance > worst && results.len() >= ef {
+                break;
+            }
+
+            let Some(links) = self.nodes[current.id].links.get(layer) else {
+                continue;
+            };
+            for &neighbor in links {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = self.candidate(query, neighbor);
+                let worst = results.peek().map_or(f32::INFINITY, |c| c.distance);
+                if results.len() < ef || candidate.distance < worst {
+                    candidates.push(Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    fn prune(&mut self, id: usize, layer: usize, max_links: usize) {
+        let vector = self.nodes[id].vector.clone();
+        let mut links: Vec<Candidate> = self.nodes[id].links[layer]
+            .iter()
+            .map(|&n| self.candidate(&vector, n))
+            .collect();
+        links.sort();
+        links.truncate(max_links);
+        self.nodes[id].links[layer] = links.into_iter().map(|c| c.id).collect();
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.params.m * 2
+        } else {
+            self.params.m
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        // xorshift64*, deterministic so rebuilds are reproducible
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545f4914f6cdd1d) >> 11;
+        let uniform = (bits as f64 + 1.0) / (1u64 << 53) as f64;
+        let level_mult = 1.0 / (self.params.m.max(2) as f64).ln();
+        (-uniform.ln() * level_mult) as usize
+    }
+
+    fn rebuild(&mut self) {
+        let live: Vec<Node> = std::mem::take(&mut self.nodes).into_iter().filter(|n| !n.deleted).collect();
+        self.ids.clear();
+        self.entry = None;
+        self.deleted = 0;
+        for node in live {
+            self.insert(&node.key, node.vector);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::normalize;
+
+    fn vector(seed: u64) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(0x9e3779b97f4a7c15) | 1;
+        let mut v: Vec<f32> = (0..16)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 1000) as f32 / 1000.0 - 0.5
+            })
+            .collect();
+        normalize(&mut v);
+        v
+    }
+
+    #[test]
+    fn test_search_matches_brute_force() {
+        let mut index = HnswIndex::new(HnswParams { m: 8, ..Default::default() });
+        for i in 0..500 {
+            index.insert(&format!("k{}", i), vector(i));
+        }
+
+        let query = vector(10_000);
+        let mut exact: Vec<(String, f32)> = (0..500)
+            .map(|i| (format!("k{}", i), cosine(&query, &vector(i))))
+            .collect();
+        exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let found = index.search(&query, 10);
+        assert_eq!(found.len(), 10);
+        let hits = found.iter().filter(|(k, _)| exact[..10].iter().any(|(e, _)| e == k)).count();
+        assert!(hits >= 9, "recall too low: {}/10", hits);
+        assert_eq!(index.search(&query, 500), exact);
+    }
+
+    #[test]
+    fn test_remove_and_rebuild() {
+        let mut index = HnswIndex::new(HnswParams::default());
+        for i in 0..20 {
+            index.insert(&format!("k{}", i), vector(i));
+        }
+        assert!(index.remove("k3"));
+        assert!(!index.remove("k3"));
+        assert!(index.search(&vector(3), 20).iter().all(|(k, _)| k != "k3"));
+
+        for i in 0..15 {
+            index.remove(&format!("k{}", i));
+        }
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.nodes.len(), index.len() + index.deleted);
+        assert_eq!(index.search(&vector(17), 1)[0].0, "k17");
+    }
+}
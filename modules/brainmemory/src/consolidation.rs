@@ -0,0 +1,287 @@
+// SimplyKI BrainMemory - Episodic to Semantic Consolidation
+// Erstellt: 2026-10-19 10:10:00 CEST
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::analysis::{is_stopword, normalize, Language};
+use crate::episode::{Episode, EpisodeId};
+use crate::text::{collect_strings, tokenize};
+
+/// Shorter tokens are not mined as keywords by default
+const MIN_KEYWORD_LEN: usize = 4;
+
+/// Keywords listed in an episode summary
+const SUMMARY_KEYWORDS: usize = 5;
+
+/// A distilled piece of knowledge and the episodes it was derived from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticFact {
+    pub key: String,
+    pub value: Value,
+    pub sources: Vec<EpisodeId>,
+}
+
+/// Turns closed episodes into semantic facts during `optimize_memory`.
+/// Receives the episodes closed since its last run; returned facts
+/// overwrite earlier ones with the same key unless their value is unchanged.
+pub trait Consolidator: fmt::Debug + Send + Sync {
+    fn consolidate(&self, episodes: &[&Episode]) -> Vec<SemanticFact>;
+}
+
+/// Consolidation step selectable at runtime (`POST /consolidator`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConsolidatorConfig {
+    Heuristic(HeuristicConsolidator),
+    /// A `SummaryConsolidator` running `summarize_episode`
+    Summary,
+    None,
+}
+
+impl Default for ConsolidatorConfig {
+    fn default() -> Self {
+        Self::Heuristic(HeuristicConsolidator::default())
+    }
+}
+
+impl ConsolidatorConfig {
+    pub fn build(&self) -> Option<Arc<dyn Consolidator>> {
+        match self {
+            Self::Heuristic(heuristic) => Some(Arc::new(heuristic.clone())),
+            Self::Summary => Some(Arc::new(SummaryConsolidator::new(summarize_episode))),
+            Self::None => None,
+        }
+    }
+}
+
+/// Keywords of an event payload: tokens of at least `min_len` characters
+/// that are no German or English stopword
+fn keywords(payload: &Value, min_len: usize) -> Vec<String> {
+    let mut strings = Vec::new();
+    collect_strings(payload, &mut strings);
+    strings
+        .into_iter()
+        .flat_map(tokenize)
+        .filter(|token| token.chars().count() >= min_len)
+        .filter(|token| {
+            let token = normalize(token);
+            !is_stopword(&token, Language::German) && !is_stopword(&token, Language::English)
+        })
+        .collect()
+}
+
+/// Local heuristics: keys, values and keywords recurring across episodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeuristicConsolidator {
+    /// Minimum number of episodes a key, value or keyword must appear in
+    pub min_support: usize,
+    pub max_keywords: usize,
+    pub min_keyword_len: usize,
+}
+
+impl Default for HeuristicConsolidator {
+    fn default() -> Self {
+        Self {
+            min_support: 2,
+            max_keywords: 20,
+            min_keyword_len: MIN_KEYWORD_LEN,
+        }
+    }
+}
+
+impl Consolidator for HeuristicConsolidator {
+    fn consolidate(&self, episodes: &[&Episode]) -> Vec<SemanticFact> {
+        let mut key_episodes: HashMap<&str, BTreeSet<EpisodeId>> = HashMap::new();
+        let mut value_episodes: HashMap<(&str, String), BTreeSet<EpisodeId>> = HashMap::new();
+        let mut keyword_episodes: HashMap<String, BTreeSet<EpisodeId>> = HashMap::new();
+
+        for episode in episodes {
+            for event in &episode.events {
+                if let Some(key) = event.key.as_deref() {
+                    key_episodes.entry(key).or_default().insert(episode.id);
+                    if !event.payload.is_null() {
+                        value_episodes
+                            .entry((key, event.payload.to_string()))
+                            .or_default()
+                            .insert(episode.id);
+                    }
+                }
+
+                for keyword in keywords(&event.payload, self.min_keyword_len) {
+                    keyword_episodes.entry(keyword).or_default().insert(episode.id);
+                }
+            }
+        }
+
+        let mut facts = Vec::new();
+
+        // Keys touched in several sessions, with their most common value if it repeats
+        for (key, ids) in &key_episodes {
+            if ids.len() < self.min_support {
+                continue;
+            }
+            let recurring_value = value_episodes
+                .iter()
+                .filter(|((k, _), vids)| k == key && vids.len() >= self.min_support)
+                .max_by(|a, b| a.1.len().cmp(&b.1.len()).then_with(|| b.0.1.cmp(&a.0.1)))
+                .and_then(|((_, v), _)| serde_json::from_str::<Value>(v).ok());
+
+            facts.push(SemanticFact {
+                key: format!("semantic/key/{}", key),
+                value: json!({
+                    "key": key,
+                    "episodes": ids.len(),
+                    "recurring_value": recurring_value,
+                }),
+                sources: ids.iter().copied().collect(),
+            });
+        }
+
+        // Keywords by the number of episodes mentioning them
+        let mut keywords: Vec<(&String, &BTreeSet<EpisodeId>)> = keyword_episodes
+            .iter()
+            .filter(|(_, ids)| ids.len() >= self.min_support)
+            .collect();
+        keywords.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+        keywords.truncate(self.max_keywords);
+
+        if !keywords.is_empty() {
+            let sources: BTreeSet<EpisodeId> = keywords.iter().flat_map(|(_, ids)| ids.iter().copied()).collect();
+            facts.push(SemanticFact {
+                key: "semantic/keywords".to_string(),
+                value: json!(keywords
+                    .iter()
+                    .map(|(term, ids)| json!({"term": term, "episodes": ids.len()}))
+                    .collect::<Vec<_>>()),
+                sources: sources.into_iter().collect(),
+            });
+        }
+
+        facts.sort_by(|a, b| a.key.cmp(&b.key));
+        facts
+    }
+}
+
+pub type SummarizeFn = dyn Fn(&Episode) -> Option<Value> + Send + Sync;
+
+/// Runs a caller-supplied summarizer on each episode and stores the result
+/// as `semantic/episode/<id>`
+#[derive(Clone)]
+pub struct SummaryConsolidator {
+    summarize: Arc<SummarizeFn>,
+}
+
+impl SummaryConsolidator {
+    pub fn new(summarize: impl Fn(&Episode) -> Option<Value> + Send + Sync + 'static) -> Self {
+        Self { summarize: Arc::new(summarize) }
+    }
+}
+
+impl fmt::Debug for SummaryConsolidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SummaryConsolidator").finish_non_exhaustive()
+    }
+}
+
+/// Built-in summary of an episode: participant, time span, event kinds,
+/// keys written and its most frequent keywords. `None` for empty episodes.
+pub fn summarize_episode(episode: &Episode) -> Option<Value> {
+    if episode.events.is_empty() {
+        return None;
+    }
+    let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut keys: BTreeSet<&str> = BTreeSet::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for event in &episode.events {
+        *kinds.entry(event.kind.as_str()).or_default() += 1;
+        keys.extend(event.key.as_deref());
+        for keyword in keywords(&event.payload, MIN_KEYWORD_LEN) {
+            *counts.entry(keyword).or_default() += 1;
+        }
+    }
+    let mut top: Vec<(String, usize)> = counts.into_iter().collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top.truncate(SUMMARY_KEYWORDS);
+
+    Some(json!({
+        "participant": episode.participant,
+        "started_at": episode.started_at,
+        "ended_at": episode.ended_at,
+        "events": episode.events.len(),
+        "kinds": kinds,
+        "keys": keys,
+        "keywords": top.into_iter().map(|(term, _)| term).collect::<Vec<_>>(),
+    }))
+}
+
+impl Consolidator for SummaryConsolidator {
+    fn consolidate(&self, episodes: &[&Episode]) -> Vec<SemanticFact> {
+        episodes
+            .iter()
+            .filter_map(|episode| {
+                (self.summarize)(episode).map(|value| SemanticFact {
+                    key: format!("semantic/episode/{}", episode.id),
+                    value,
+                    sources: vec![episode.id],
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::episode::EpisodeStore;
+
+    #[test]
+    fn test_heuristic_finds_recurring_keys_and_keywords() {
+        let mut store = EpisodeStore::new();
+        for _ in 0..2 {
+            let id = store.begin("claude");
+            store.append(id, "store", Some("build_cmd"), json!("cargo build --release with this")).unwrap();
+            store.close(id).unwrap();
+        }
+        let once = store.begin("claude");
+        store.append(once, "store", Some("single"), json!("nothing recurring")).unwrap();
+        store.close(once).unwrap();
+
+        let episodes = store.query(&Default::default());
+        let facts = HeuristicConsolidator::default().consolidate(&episodes);
+
+        let key_fact = facts.iter().find(|f| f.key == "semantic/key/build_cmd").unwrap();
+        assert_eq!(key_fact.sources.len(), 2);
+        assert_eq!(key_fact.value["recurring_value"], json!("cargo build --release with this"));
+        assert!(!facts.iter().any(|f| f.key == "semantic/key/single"));
+
+        let keywords = facts.iter().find(|f| f.key == "semantic/keywords").unwrap();
+        assert!(keywords.value.as_array().unwrap().iter().any(|k| k["term"] == "cargo"));
+        assert!(!keywords.value.as_array().unwrap().iter().any(|k| k["term"] == "with" || k["term"] == "this"));
+    }
+
+    #[test]
+    fn test_summary_consolidator() {
+        let mut store = EpisodeStore::new();
+        let id = store.begin("claude");
+        store.append(id, "store", Some("notes/ram"), json!("Arbeitsspeicher voll, Arbeitsspeicher prüfen")).unwrap();
+        store.close(id).unwrap();
+        let empty = store.begin("claude");
+        store.close(empty).unwrap();
+
+        let config: ConsolidatorConfig = serde_json::from_value(json!({"kind": "summary"})).unwrap();
+        let facts = config.build().unwrap().consolidate(&store.query(&Default::default()));
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].key, format!("semantic/episode/{}", id));
+        assert_eq!(facts[0].sources, vec![id]);
+        assert_eq!(facts[0].value["keys"], json!(["notes/ram"]));
+        assert_eq!(facts[0].value["keywords"][0], "arbeitsspeicher");
+
+        let config: ConsolidatorConfig = serde_json::from_value(json!({"kind": "heuristic", "min_support": 3})).unwrap();
+        assert!(matches!(config, ConsolidatorConfig::Heuristic(HeuristicConsolidator { min_support: 3, max_keywords: 20, .. })));
+    }
+}
@@ -0,0 +1,215 @@
+// SimplyKI BrainMemory - Content-Addressed Value Store
+// Erstellt: 2026-10-19 11:02:00 CEST
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ContentHash(pub u64);
+
+#[derive(Debug, Clone)]
+struct Blob {
+    value: Arc<Value>,
+    size: usize,
+    refs: usize,
+}
+
+/// Values shared by every entry with identical JSON, reference counted
+/// across working and long-term memory. Hash collisions probe the next
+/// slot; a freed slot inside a probe chain stays as a tombstone (`None`)
+/// so values further down the chain are still found.
+#[derive(Debug, Clone, Default)]
+pub struct ContentStore {
+    blobs: HashMap<ContentHash, Option<Blob>>,
+    stored_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupInfo {
+    pub unique_values: usize,
+    pub references: usize,
+    pub logical_bytes: usize,
+    pub stored_bytes: usize,
+    /// logical_bytes / stored_bytes, 1.0 when nothing is shared
+    pub dedup_ratio: f64,
+}
+
+impl ContentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Slot of `value`: `Ok` if it is stored already, `Err` with the free
+    /// slot it would take otherwise
+    fn find(&self, value: &Value, canonical: &str) -> Result<ContentHash, ContentHash> {
+        // serde_json objects are key-sorted, so equal values serialize equally
+        let mut hasher = DefaultHasher::new();
+        canonical.hash(&mut hasher);
+        let mut hash = ContentHash(hasher.finish());
+        let mut tombstone = None;
+
+        loop {
+            match self.blobs.get(&hash) {
+                Some(Some(blob)) if *blob.value == *value => return Ok(hash),
+                // Hash collision with a different value: probe the next slot
+                Some(Some(_)) => {}
+                Some(None) => {
+                    tombstone.get_or_insert(hash);
+                }
+                None => return Err(tombstone.unwrap_or(hash)),
+            }
+            hash = ContentHash(hash.0.wrapping_add(1));
+        }
+    }
+
+    fn blob(&self, hash: ContentHash) -> Option<&Blob> {
+        self.blobs.get(&hash).and_then(Option::as_ref)
+    }
+
+    /// Add a reference to `value`, sharing the existing copy if there is one
+    pub fn insert(&mut self, value: Value) -> (ContentHash, Arc<Value>) {
+        let canonical = value.to_string();
+        match self.find(&value, &canonical) {
+            Ok(hash) => {
+                let blob = self.blobs.get_mut(&hash).and_then(Option::as_mut).expect("found blob exists");
+                blob.refs += 1;
+                (hash, Arc::clone(&blob.value))
+            }
+            Err(hash) => {
+                self.stored_bytes += canonical.len();
+                let value = Arc::new(value);
+                self.blobs.insert(hash, Some(Blob {
+                    value: Arc::clone(&value),
+                    size: canonical.len(),
+                    refs: 1,
+                }));
+                (hash, value)
+            }
+        }
+    }
+
+    /// Bytes `stored_bytes` grows by when `value` is inserted and the
+    /// reference `replacing` released; 0 if the value is shared or the
+    /// write shrinks the store
+    pub fn added_bytes(&self, value: &Value, replacing: Option<ContentHash>) -> usize {
+        let canonical = value.to_string();
+        if self.find(value, &canonical).is_ok() {
+            return 0;
+        }
+        let freed = replacing
+            .and_then(|hash| self.blob(hash))
+            .filter(|blob| blob.refs == 1)
+            .map_or(0, |blob| blob.size);
+        canonical.len().saturating_sub(freed)
+    }
+
+    /// Drop one reference; the value is freed with its last reference
+    pub fn release(&mut self, hash: ContentHash) {
+        let Some(slot) = self.blobs.get_mut(&hash) else {
+            return;
+        };
+        let Some(blob) = slot else {
+            return;
+        };
+        blob.refs -= 1;
+        if blob.refs > 0 {
+            return;
+        }
+        self.stored_bytes -= blob.size;
+        *slot = None;
+
+        // Tombstones at the end of a chain end no probe early, drop them
+        let mut end = hash;
+        while matches!(self.blobs.get(&end), Some(None))
+            && !self.blobs.contains_key(&ContentHash(end.0.wrapping_add(1)))
+        {
+            self.blobs.remove(&end);
+            end = ContentHash(end.0.wrapping_sub(1));
+        }
+    }
+
+    pub fn get(&self, hash: ContentHash) -> Option<&Arc<Value>> {
+        self.blob(hash).map(|b| &b.value)
+    }
+
+    /// Bytes held by distinct values
+    pub fn stored_bytes(&self) -> usize {
+        self.stored_bytes
+    }
+
+    pub fn stats(&self) -> DedupInfo {
+        let blobs = || self.blobs.values().flatten();
+        let references = blobs().map(|b| b.refs).sum();
+        let logical_bytes = blobs().map(|b| b.size * b.refs).sum();
+        let stored_bytes = self.stored_bytes;
+        DedupInfo {
+            unique_values: blobs().count(),
+            references,
+            logical_bytes,
+            stored_bytes,
+            dedup_ratio: if stored_bytes == 0 {
+                1.0
+            } else {
+                logical_bytes as f64 / stored_bytes as f64
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_values_are_shared() {
+        let mut store = ContentStore::new();
+        let (a, va) = store.insert(serde_json::json!({"x": 1, "y": [1, 2]}));
+        let (b, vb) = store.insert(serde_json::json!({"y": [1, 2], "x": 1}));
+        let (c, _) = store.insert(serde_json::json!("other"));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(Arc::ptr_eq(&va, &vb));
+
+        let stats = store.stats();
+        assert_eq!(stats.unique_values, 2);
+        assert_eq!(stats.references, 3);
+        assert!(stats.dedup_ratio > 1.0);
+
+        store.release(a);
+        assert!(store.get(a).is_some());
+        store.release(a);
+        assert!(store.get(a).is_none());
+        assert_eq!(store.stats().unique_values, 1);
+        assert_eq!(store.stored_bytes(), "\"other\"".len());
+    }
+
+    #[test]
+    fn test_collisions_survive_release() {
+        let mut store = ContentStore::new();
+        let value = serde_json::json!("value");
+        let Err(home) = store.find(&value, &value.to_string()) else {
+            panic!("empty store holds nothing");
+        };
+        // Another value already in the home slot, as after a collision
+        let other = Blob { value: Arc::new(serde_json::json!("other")), size: 7, refs: 1 };
+        store.blobs.insert(home, Some(other));
+        store.stored_bytes += 7;
+
+        let (moved, _) = store.insert(value.clone());
+        assert_eq!(moved, ContentHash(home.0.wrapping_add(1)));
+        store.release(home);
+        assert_eq!(store.insert(value.clone()).0, moved);
+        assert_eq!(store.stats().references, 2);
+
+        // Freeing the end of the chain drops its tombstones too
+        store.release(moved);
+        store.release(moved);
+        assert!(store.blobs.is_empty());
+        assert_eq!(store.stored_bytes(), 0);
+    }
+}
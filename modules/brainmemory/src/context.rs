@@ -0,0 +1,207 @@
+// SimplyKI BrainMemory - Context Assembly
+// Erstellt: 2026-10-19 19:10:00 CEST
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::embedding::Precomputed;
+use crate::search::{Ranking, SearchMode, Tier};
+use crate::text::tokenize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ContextOptions {
+    /// Estimated tokens the packed entries may use in total
+    pub budget: usize,
+    /// Search results considered for packing
+    pub candidates: usize,
+    pub mode: SearchMode,
+    pub ranking: Ranking,
+    pub tier: Option<Tier>,
+    /// Token overlap (Jaccard) from which an entry counts as a duplicate
+    /// of a higher ranked one
+    pub duplicate_threshold: f64,
+    /// Embedding of the task computed ahead, see `Precomputed`
+    #[serde(skip)]
+    pub task_embedding: Option<Precomputed>,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            budget: 4000,
+            candidates: 50,
+            mode: SearchMode::Hybrid,
+            ranking: Ranking { recency: 0.5, frequency: 0.25, association: 0.5, ..Default::default() },
+            tier: None,
+            duplicate_threshold: 0.8,
+            task_embedding: None,
+        }
+    }
+}
+
+/// A ranked entry offered to `pack`
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub key: String,
+    pub score: f64,
+    /// The value as it should appear in the prompt, see `render`
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextItem {
+    pub key: String,
+    pub score: f64,
+    /// `key: content`, one block of the assembled context
+    pub text: String,
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum DropReason {
+    /// Too similar to an entry already packed
+    Duplicate { of: String },
+    /// Did not fit into the remaining budget
+    OverBudget,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DroppedItem {
+    pub key: String,
+    pub score: f64,
+    pub tokens: usize,
+    #[serde(flatten)]
+    pub reason: DropReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextPack {
+    /// Item texts in rank order, separated by blank lines
+    pub context: String,
+    pub items: Vec<ContextItem>,
+    pub dropped: Vec<DroppedItem>,
+    /// Sum of the item estimates
+    pub tokens: usize,
+    pub budget: usize,
+}
+
+/// Rough token count for LLM prompts: about four characters per token,
+/// but never fewer tokens than words
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4).max(text.split_whitespace().count())
+}
+
+/// Prompt form of a value: strings as they are, anything else as compact JSON
+pub fn render(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Greedily pack candidates in rank order. Near duplicates of packed
+/// entries are skipped, and entries that do not fit leave room for
+/// smaller ones further down.
+pub fn pack(mut candidates: Vec<Candidate>, options: &ContextOptions) -> ContextPack {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.key.cmp(&b.key)));
+
+    let mut items: Vec<ContextItem> = Vec::new();
+    let mut packed_tokens: Vec<HashSet<String>> = Vec::new();
+    let mut dropped = Vec::new();
+    let mut tokens = 0;
+
+    for candidate in candidates {
+        let text = format!("{}: {}", candidate.key, candidate.content);
+        let estimate = estimate_tokens(&text);
+        let words: HashSet<String> = tokenize(&candidate.content).into_iter().collect();
+
+        let duplicate = items
+            .iter()
+            .zip(&packed_tokens)
+            .find(|(_, other)| jaccard(&words, other) >= options.duplicate_threshold)
+            .map(|(item, _)| item.key.clone());
+        let reason = match duplicate {
+            Some(of) => Some(DropReason::Duplicate { of }),
+            None if tokens + estimate > options.budget => Some(DropReason::OverBudget),
+            None => None,
+        };
+
+        match reason {
+            Some(reason) => dropped.push(DroppedItem {
+                key: candidate.key,
+                score: candidate.score,
+                tokens: estimate,
+                reason,
+            }),
+            None => {
+                tokens += estimate;
+                packed_tokens.push(words);
+                items.push(ContextItem { key: candidate.key, score: candidate.score, text, tokens: estimate });
+            }
+        }
+    }
+
+    ContextPack {
+        context: items.iter().map(|i| i.text.as_str()).collect::<Vec<_>>().join("\n\n"),
+        items,
+        dropped,
+        tokens,
+        budget: options.budget,
+    }
+}
+
+/// Word overlap of two texts; a text without words duplicates nothing
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(key: &str, score: f64, content: &str) -> Candidate {
+        Candidate { key: key.to_string(), score, content: content.to_string() }
+    }
+
+    #[test]
+    fn test_pack_respects_budget_and_dedups() {
+        let options = ContextOptions { budget: 20, ..Default::default() };
+        let long = "word ".repeat(30);
+        let pack = pack(
+            vec![
+                candidate("low", 0.5, "small note"),
+                candidate("top", 3.0, "the server stores memories"),
+                candidate("copy", 2.0, "The server stores memories!"),
+                candidate("long", 1.0, &long),
+            ],
+            &options,
+        );
+
+        let keys: Vec<&str> = pack.items.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(keys, vec!["top", "low"]);
+        assert!(pack.tokens <= options.budget);
+        assert_eq!(pack.context, "top: the server stores memories\n\nlow: small note");
+        assert_eq!(pack.dropped[0].reason, DropReason::Duplicate { of: "top".to_string() });
+        assert_eq!(pack.dropped[1].reason, DropReason::OverBudget);
+    }
+
+    #[test]
+    fn test_wordless_items_are_not_duplicates() {
+        let pack = pack(vec![candidate("a", 2.0, "..."), candidate("b", 1.0, "--")], &ContextOptions::default());
+        assert_eq!(pack.items.len(), 2);
+        assert!(pack.dropped.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("a b c d e"), 5);
+    }
+}
@@ -0,0 +1,174 @@
+// SimplyKI BrainMemory - Text Embeddings
+// Erstellt: 2026-10-19 15:20:00 CEST
+
+use std::fmt;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+use crate::text::{fnv1a, tokenize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbedError {
+    pub message: String,
+}
+
+impl EmbedError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for EmbedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "embedding failed: {}", self.message)
+    }
+}
+
+impl std::error::Error for EmbedError {}
+
+/// Turns text into fixed-size vectors. Vectors should be L2-normalized so
+/// that the dot product is the cosine similarity.
+pub trait Embedder: fmt::Debug + Send + Sync {
+    fn dimensions(&self) -> usize;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, EmbedError>;
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        texts.iter().map(|t| self.embed(t)).collect()
+    }
+}
+
+/// Offline embedder using signed feature hashing of words and character
+/// trigrams. Needs no model download; trigrams make it tolerant to
+/// inflection, typos and compound words.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashingEmbedder {
+    pub dimensions: usize,
+    /// Weight of character trigrams relative to whole words
+    pub trigram_weight: f32,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self {
+            dimensions: 256,
+            trigram_weight: 0.5,
+        }
+    }
+}
+
+impl HashingEmbedder {
+    fn add(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let slot = (hash % self.dimensions as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[slot] += sign * weight;
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, EmbedError> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        for word in tokenize(text) {
+            self.add(&mut vector, &word, 1.0);
+
+            let padded: Vec<char> = format!("<{}>", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add(&mut vector, &format!("#{}", trigram), self.trigram_weight);
+            }
+        }
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+/// An embedding computed before the call that needs it, so that slow
+/// embedders can run while the memory is not locked. It is only used while
+/// the memory still has the same embedder and the text is unchanged;
+/// otherwise the text is embedded again in place.
+#[derive(Debug, Clone)]
+pub struct Precomputed {
+    embedder: Arc<dyn Embedder>,
+    text: String,
+    vector: Result<Vec<f32>, EmbedError>,
+}
+
+impl Precomputed {
+    pub fn new(embedder: Arc<dyn Embedder>, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let vector = embedder.embed(&text);
+        Self { embedder, text, vector }
+    }
+
+    /// Embed many texts in batches of `batch`
+    pub fn batch(embedder: &Arc<dyn Embedder>, texts: Vec<String>, batch: usize) -> Vec<Self> {
+        let mut embedded = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(batch.max(1)) {
+            let refs: Vec<&str> = chunk.iter().map(String::as_str).collect();
+            let vectors: Vec<Result<Vec<f32>, EmbedError>> = match embedder.embed_batch(&refs) {
+                Ok(vectors) => vectors.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e); chunk.len()],
+            };
+            for (text, vector) in chunk.iter().zip(vectors) {
+                embedded.push(Self { embedder: Arc::clone(embedder), text: text.clone(), vector });
+            }
+        }
+        embedded
+    }
+
+    /// The result if it was computed by `embedder` for `text`
+    pub fn get(&self, embedder: &Arc<dyn Embedder>, text: &str) -> Option<&Result<Vec<f32>, EmbedError>> {
+        (Arc::ptr_eq(&self.embedder, embedder) && self.text == text).then_some(&self.vector)
+    }
+}
+
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in vector.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashing_embedder_similarity() {
+        let embedder = HashingEmbedder::default();
+        let a = embedder.embed("Der Arbeitsspeicher ist voll").unwrap();
+        let b = embedder.embed("arbeitsspeicher voll").unwrap();
+        let c = embedder.embed("tokio warp server").unwrap();
+
+        assert_eq!(a.len(), 256);
+        assert!((cosine(&a, &a) - 1.0).abs() < 1e-5);
+        assert!(cosine(&a, &b) > cosine(&a, &c));
+        assert_eq!(embedder.embed("").unwrap(), vec![0.0; 256]);
+    }
+
+    #[test]
+    fn test_precomputed_matches_embedder_and_text() {
+        let embedder: Arc<dyn Embedder> = Arc::new(HashingEmbedder::default());
+        let other: Arc<dyn Embedder> = Arc::new(HashingEmbedder::default());
+        let precomputed = Precomputed::new(Arc::clone(&embedder), "tokio warp server");
+
+        assert!(precomputed.get(&embedder, "tokio warp server").is_some_and(|v| v.is_ok()));
+        assert!(precomputed.get(&embedder, "something else").is_none());
+        assert!(precomputed.get(&other, "tokio warp server").is_none());
+
+        let batch = Precomputed::batch(&embedder, vec!["a".into(), "b".into(), "c".into()], 2);
+        assert_eq!(batch.len(), 3);
+        assert!(batch[2].get(&embedder, "c").is_some());
+    }
+}
@@ -0,0 +1,338 @@
+// SimplyKI BrainMemory - Entity Extraction
+// Erstellt: 2026-10-19 22:40:00 CEST
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::analysis::{is_stopword, Language};
+use crate::text::collect_strings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Url,
+    /// Language model names like `claude-3.5-sonnet` or `gpt-4o`
+    Model,
+    /// Called or defined functions and `a::b` paths
+    Function,
+    /// File names with an extension and paths with a `/`
+    Path,
+    /// Hex strings of 7 to 40 characters with letters and digits
+    Sha,
+    /// `#123`
+    Issue,
+    /// Runs of capitalized words
+    Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    /// As first seen; lookups ignore case
+    pub text: String,
+}
+
+/// Rules in priority order; text claimed by one rule is skipped by the rest.
+/// A capture group, where present, is the entity.
+fn rules() -> &'static [(EntityKind, Regex)] {
+    static RULES: OnceLock<Vec<(EntityKind, Regex)>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let rule = |kind, pattern: &str| (kind, Regex::new(pattern).expect("entity patterns are valid"));
+        vec![
+            rule(EntityKind::Url, r#"(?i)\bhttps?://[^\s<>"'`]+"#),
+            rule(
+                EntityKind::Model,
+                r"(?i)\b(?:claude|gpt|gemini|llama|mistral|mixtral|qwen|deepseek|phi|o[1-9])(?:-[a-z0-9][a-z0-9.]*)+",
+            ),
+            rule(EntityKind::Function, r"\b(?:fn|def|func|function)\s+([A-Za-z_]\w*)"),
+            rule(EntityKind::Function, r"\b([A-Za-z_]\w*(?:(?:::|\.)[A-Za-z_]\w*)*)\("),
+            rule(EntityKind::Function, r"\b[A-Za-z_]\w*(?:::[A-Za-z_]\w*)+"),
+            rule(EntityKind::Path, r"(?:~|\.{1,2})?/?[\w-][\w.-]*(?:/[\w.-]+)*"),
+            rule(EntityKind::Sha, r"\b[0-9a-f]{7,40}\b"),
+            rule(EntityKind::Issue, r"(?:^|[^\w&])(#\d+)\b"),
+            rule(EntityKind::Name, r"\b[A-Z]\w*[a-z]\w*(?:[ \t]+[A-Z]\w*[a-z]\w*)*"),
+        ]
+    })
+}
+
+/// Entities mentioned in `text`, in order of appearance, repeats included
+pub fn extract(text: &str) -> Vec<Entity> {
+    let mut found: Vec<(usize, Entity)> = Vec::new();
+    let mut claimed: Vec<(usize, usize)> = Vec::new();
+
+    for (kind, regex) in rules() {
+        for captures in regex.captures_iter(text) {
+            let m = captures.get(1).or_else(|| captures.get(0)).expect("group 0 always matches");
+            let Some((start, entity)) = accept(*kind, text, m.start(), m.as_str()) else {
+                continue;
+            };
+            let end = start + entity.len();
+            if claimed.iter().any(|&(s, e)| start < e && s < end) {
+                continue;
+            }
+            claimed.push((start, end));
+            found.push((start, Entity { kind: *kind, text: entity.to_string() }));
+        }
+    }
+
+    found.sort_by_key(|(start, _)| *start);
+    found.into_iter().map(|(_, entity)| entity).collect()
+}
+
+/// Trim and validate a match; returns the entity's start and text
+fn accept<'a>(kind: EntityKind, text: &str, start: usize, matched: &'a str) -> Option<(usize, &'a str)> {
+    match kind {
+        EntityKind::Url => Some((start, matched.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}', '\'', '"']))),
+        EntityKind::Model => Some((start, matched.trim_end_matches('.'))),
+        EntityKind::Path => {
+            let path = matched.trim_end_matches(['.', '-']);
+            is_path(path).then_some((start, path))
+        }
+        EntityKind::Sha => {
+            let mixed = matched.bytes().any(|b| b.is_ascii_digit()) && matched.bytes().any(|b| b.is_ascii_lowercase());
+            mixed.then_some((start, matched))
+        }
+        EntityKind::Name => {
+            // Leading function words are no part of the name
+            let mut words: Vec<(usize, &str)> = matched.split_whitespace().map(|w| (offset(matched, w), w)).collect();
+            let leading = words.iter().take_while(|(_, w)| is_function_word(w)).count();
+            words.drain(..leading);
+            let (&(first, word), &(last, last_word)) = (words.first()?, words.last()?);
+            let interior_upper = word.chars().skip(1).any(|c| c.is_uppercase());
+            if words.len() == 1 && leading == 0 && at_sentence_start(&text[..start]) && !interior_upper {
+                return None;
+            }
+            Some((start + first, &matched[first..last + last_word.len()]))
+        }
+        EntityKind::Function | EntityKind::Issue => Some((start, matched)),
+    }
+}
+
+/// Paths need a `/` and either a root or a file name with an extension;
+/// bare file names need an extension and at least two characters before it
+fn is_path(path: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let has_extension = file.rsplit_once('.').is_some_and(|(name, extension)| {
+        !name.is_empty()
+            && !extension.is_empty()
+            && extension.len() <= 8
+            && extension.starts_with(|c: char| c.is_ascii_alphabetic())
+            && extension.chars().all(|c| c.is_ascii_alphanumeric())
+    });
+    if path.contains('/') {
+        let rooted = path.starts_with(['/', '~', '.']);
+        (rooted && path.len() > 1) || has_extension
+    } else {
+        has_extension && file.split('.').next().is_some_and(|name| name.chars().count() >= 2)
+    }
+}
+
+fn is_function_word(word: &str) -> bool {
+    let word = word.to_lowercase();
+    is_stopword(&word, Language::English) || is_stopword(&word, Language::German)
+}
+
+fn at_sentence_start(before: &str) -> bool {
+    let trimmed = before.trim_end();
+    trimmed.is_empty() || trimmed.ends_with(['.', '!', '?', ':']) || before[trimmed.len()..].contains('\n')
+}
+
+/// Byte offset of a subslice within its parent
+fn offset(parent: &str, part: &str) -> usize {
+    part.as_ptr() as usize - parent.as_ptr() as usize
+}
+
+/// An indexed entity with the keys and mentions counted for it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntityCount {
+    #[serde(flatten)]
+    pub entity: Entity,
+    /// Keys mentioning the entity
+    pub keys: usize,
+    pub mentions: usize,
+}
+
+/// A key mentioning an entity looked up with `EntityIndex::mentions`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntityMention {
+    pub key: String,
+    pub count: usize,
+    /// Entities that matched, e.g. `server.rs` and `src/server.rs`
+    pub forms: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Indexed {
+    entity: Entity,
+    keys: HashMap<String, usize>,
+}
+
+/// Keys by the entities their string values mention
+#[derive(Debug, Clone, Default)]
+pub struct EntityIndex {
+    entities: HashMap<String, Indexed>,
+    /// Lowercased entities per key, in order of first mention
+    by_key: HashMap<String, Vec<String>>,
+}
+
+impl EntityIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: &Value) {
+        self.remove(key);
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+
+        let mut ids: Vec<String> = Vec::new();
+        for entity in strings.iter().flat_map(|s| extract(s)) {
+            let id = entity.text.to_lowercase();
+            let indexed = self.entities.entry(id.clone()).or_insert_with(|| Indexed { entity, keys: HashMap::new() });
+            *indexed.keys.entry(key.to_string()).or_insert(0) += 1;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        if !ids.is_empty() {
+            self.by_key.insert(key.to_string(), ids);
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        for id in self.by_key.remove(key).into_iter().flatten() {
+            if let Some(indexed) = self.entities.get_mut(&id) {
+                indexed.keys.remove(key);
+                if indexed.keys.is_empty() {
+                    self.entities.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Entities of one key in order of first mention
+    pub fn of(&self, key: &str) -> Vec<Entity> {
+        self.by_key
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.entities.get(id))
+            .map(|indexed| indexed.entity.clone())
+            .collect()
+    }
+
+    /// Known entities, optionally of one kind, mentioned by most keys first
+    pub fn list(&self, kind: Option<EntityKind>, limit: usize) -> Vec<EntityCount> {
+        let mut counts: Vec<EntityCount> = self
+            .entities
+            .values()
+            .filter(|indexed| kind.is_none_or(|k| indexed.entity.kind == k))
+            .map(|indexed| EntityCount {
+                entity: indexed.entity.clone(),
+                keys: indexed.keys.len(),
+                mentions: indexed.keys.values().sum(),
+            })
+            .collect();
+        counts.sort_by(|a, b| b.keys.cmp(&a.keys).then_with(|| a.entity.text.cmp(&b.entity.text)));
+        counts.truncate(limit);
+        counts
+    }
+
+    /// Keys mentioning the entity, ignoring case, most mentions first.
+    /// A file name also finds the paths ending in it.
+    pub fn mentions(&self, entity: &str) -> Vec<EntityMention> {
+        let wanted = entity.trim().to_lowercase();
+        if wanted.is_empty() {
+            return Vec::new();
+        }
+
+        let mut by_key: HashMap<&String, EntityMention> = HashMap::new();
+        for (id, indexed) in &self.entities {
+            if !entity_matches(id, indexed.entity.kind, &wanted) {
+                continue;
+            }
+            for (key, count) in &indexed.keys {
+                let mention = by_key.entry(key).or_insert_with(|| EntityMention { key: key.clone(), count: 0, forms: Vec::new() });
+                mention.count += count;
+                mention.forms.push(indexed.entity.text.clone());
+            }
+        }
+
+        let mut mentions: Vec<EntityMention> = by_key.into_values().collect();
+        for mention in &mut mentions {
+            mention.forms.sort();
+        }
+        mentions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        mentions
+    }
+
+    /// Whether the key mentions the entity, by the rules of `mentions`
+    pub fn mentioned_by(&self, key: &str, entity: &str) -> bool {
+        let wanted = entity.trim().to_lowercase();
+        self.by_key.get(key).is_some_and(|ids| {
+            ids.iter()
+                .any(|id| self.entities.get(id).is_some_and(|i| entity_matches(id, i.entity.kind, &wanted)))
+        })
+    }
+}
+
+/// Equal ignoring case, or a path ending in the wanted file or path
+fn entity_matches(id: &str, kind: EntityKind, wanted: &str) -> bool {
+    id == wanted
+        || (kind == EntityKind::Path
+            && id.strip_suffix(wanted.trim_start_matches('/')).is_some_and(|rest| rest.ends_with('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kinds(text: &str) -> Vec<(EntityKind, String)> {
+        extract(text).into_iter().map(|e| (e.kind, e.text)).collect()
+    }
+
+    #[test]
+    fn test_extract() {
+        let text = "Alice Smith fixed handle_search() in src/server.rs (see #42, commit 3e42f19). \
+                    Docs: https://docs.rs/warp/latest/warp/. Tested with claude-3.5-sonnet and BrainMemory::store.";
+        assert_eq!(
+            kinds(text),
+            vec![
+                (EntityKind::Name, "Alice Smith".to_string()),
+                (EntityKind::Function, "handle_search".to_string()),
+                (EntityKind::Path, "src/server.rs".to_string()),
+                (EntityKind::Issue, "#42".to_string()),
+                (EntityKind::Sha, "3e42f19".to_string()),
+                (EntityKind::Url, "https://docs.rs/warp/latest/warp/".to_string()),
+                (EntityKind::Model, "claude-3.5-sonnet".to_string()),
+                (EntityKind::Function, "BrainMemory::store".to_string()),
+            ]
+        );
+
+        // Sentence-initial words, abbreviations and plain numbers are no entities
+        assert!(kinds("The build failed, e.g. twice in 2026. Version 1.5 and/or 1234567 too.").is_empty());
+        assert_eq!(kinds("Ask the Rust Foundation")[0].1, "Rust Foundation");
+    }
+
+    #[test]
+    fn test_index_mentions() {
+        let mut index = EntityIndex::new();
+        index.insert("a", &json!({"note": "server.rs handles routes, server.rs again"}));
+        index.insert("b", &json!("changed modules/brainmemory/src/server.rs"));
+        index.insert("c", &json!("see memory.rs and server.rs"));
+
+        let keys = |index: &EntityIndex, entity: &str| index.mentions(entity).into_iter().map(|m| m.key).collect::<Vec<_>>();
+        assert_eq!(keys(&index, "Server.rs"), vec!["a", "b", "c"]);
+        assert_eq!(keys(&index, "src/server.rs"), vec!["b"]);
+        assert!(index.mentioned_by("b", "server.rs"));
+        assert_eq!(index.list(Some(EntityKind::Path), 10)[0].entity.text, "server.rs");
+
+        index.remove("a");
+        assert_eq!(keys(&index, "server.rs"), vec!["b", "c"]);
+        assert!(index.of("a").is_empty());
+    }
+}
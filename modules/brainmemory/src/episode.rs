@@ -0,0 +1,224 @@
+// SimplyKI BrainMemory - Episodic Memory
+// Erstellt: 2026-10-19 09:12:00 CEST
+
+use std::collections::BTreeMap;
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub type EpisodeId = u64;
+
+/// One agent session with its events in the order they happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    pub id: EpisodeId,
+    pub participant: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub events: Vec<EpisodeEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeEvent {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub kind: String,
+    pub key: Option<String>,
+    pub payload: Value,
+}
+
+/// Filter for `BrainMemory::query_episodes`; all fields are optional
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EpisodeQuery {
+    pub participant: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub closed_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpisodeError {
+    NotFound(EpisodeId),
+    AlreadyClosed(EpisodeId),
+}
+
+impl fmt::Display for EpisodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpisodeError::NotFound(id) => write!(f, "episode {} not found", id),
+            EpisodeError::AlreadyClosed(id) => write!(f, "episode {} is already closed", id),
+        }
+    }
+}
+
+impl std::error::Error for EpisodeError {}
+
+impl Episode {
+    pub fn is_closed(&self) -> bool {
+        self.ended_at.is_some()
+    }
+
+    /// True if the episode overlaps the half-open range `[from, to)`
+    fn overlaps(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> bool {
+        let end = self.ended_at.unwrap_or_else(Utc::now);
+        if let Some(from) = from {
+            if end < from {
+                return false;
+            }
+        }
+        if let Some(to) = to {
+            if self.started_at >= to {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Render the episode as a section for a NEUSTART-*.md handoff file
+    pub fn to_neustart_markdown(&self) -> String {
+        let mut out = format!(
+            "## 🧠 Episode {}: {}\n\n- Start: {}\n",
+            self.id,
+            self.participant,
+            self.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        match self.ended_at {
+            Some(end) => out.push_str(&format!("- Ende: {}\n", end.format("%Y-%m-%d %H:%M:%S"))),
+            None => out.push_str("- Ende: (läuft noch)\n"),
+        }
+        out.push_str(&format!("- Events: {}\n\n### Verlauf\n", self.events.len()));
+        for event in &self.events {
+            let time = event.timestamp.format("%H:%M:%S");
+            match &event.key {
+                Some(key) => out.push_str(&format!("- [{}] {} `{}`\n", time, event.kind, key)),
+                None => out.push_str(&format!("- [{}] {}\n", time, event.kind)),
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EpisodeStore {
+    episodes: BTreeMap<EpisodeId, Episode>,
+    next_id: EpisodeId,
+}
+
+impl EpisodeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin(&mut self, participant: &str) -> EpisodeId {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.episodes.insert(id, Episode {
+            id,
+            participant: participant.to_string(),
+            started_at: Utc::now(),
+            ended_at: None,
+            events: Vec::new(),
+        });
+        id
+    }
+
+    pub fn append(
+        &mut self,
+        id: EpisodeId,
+        kind: &str,
+        key: Option<&str>,
+        payload: Value,
+    ) -> Result<u64, EpisodeError> {
+        let episode = self.episodes.get_mut(&id).ok_or(EpisodeError::NotFound(id))?;
+        if episode.is_closed() {
+            return Err(EpisodeError::AlreadyClosed(id));
+        }
+
+        let seq = episode.events.len() as u64;
+        episode.events.push(EpisodeEvent {
+            seq,
+            timestamp: Utc::now(),
+            kind: kind.to_string(),
+            key: key.map(|k| k.to_string()),
+            payload,
+        });
+        Ok(seq)
+    }
+
+    pub fn close(&mut self, id: EpisodeId) -> Result<(), EpisodeError> {
+        let episode = self.episodes.get_mut(&id).ok_or(EpisodeError::NotFound(id))?;
+        if episode.is_closed() {
+            return Err(EpisodeError::AlreadyClosed(id));
+        }
+        episode.ended_at = Some(Utc::now());
+        Ok(())
+    }
+
+    pub fn get(&self, id: EpisodeId) -> Option<&Episode> {
+        self.episodes.get(&id)
+    }
+
+    /// Matching episodes, oldest first
+    pub fn query(&self, query: &EpisodeQuery) -> Vec<&Episode> {
+        let mut results: Vec<&Episode> = self.episodes
+            .values()
+            .filter(|e| !query.closed_only || e.is_closed())
+            .filter(|e| query.participant.as_ref().is_none_or(|p| &e.participant == p))
+            .filter(|e| e.overlaps(query.from, query.to))
+            .collect();
+
+        results.sort_by_key(|e| (e.started_at, e.id));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_episode_lifecycle() {
+        let mut store = EpisodeStore::new();
+        let id = store.begin("claude");
+
+        assert_eq!(store.append(id, "store", Some("a"), Value::Null), Ok(0));
+        assert_eq!(store.append(id, "note", None, serde_json::json!("hi")), Ok(1));
+        store.close(id).unwrap();
+
+        assert_eq!(store.append(id, "store", Some("b"), Value::Null), Err(EpisodeError::AlreadyClosed(id)));
+        assert_eq!(store.close(id), Err(EpisodeError::AlreadyClosed(id)));
+        assert_eq!(store.close(99), Err(EpisodeError::NotFound(99)));
+
+        let episode = store.get(id).unwrap();
+        let seqs: Vec<u64> = episode.events.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, vec![0, 1]);
+        assert!(episode.to_neustart_markdown().contains("`a`"));
+    }
+
+    #[test]
+    fn test_episode_query() {
+        let mut store = EpisodeStore::new();
+        let a = store.begin("claude");
+        let b = store.begin("gpt");
+        store.close(a).unwrap();
+
+        let by_participant = store.query(&EpisodeQuery {
+            participant: Some("gpt".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(by_participant.len(), 1);
+        assert_eq!(by_participant[0].id, b);
+
+        let closed = store.query(&EpisodeQuery { closed_only: true, ..Default::default() });
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].id, a);
+
+        let future = store.query(&EpisodeQuery {
+            from: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        });
+        assert!(future.is_empty());
+    }
+}
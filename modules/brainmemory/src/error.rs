@@ -0,0 +1,79 @@
+// SimplyKI BrainMemory - Error Types
+// Erstellt: 2026-10-19 11:55:00 CEST
+
+use std::fmt;
+
+use crate::episode::EpisodeError;
+use crate::schema::SchemaViolation;
+
+/// Why `BrainMemory::store` refused a write
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreError {
+    InvalidKey {
+        key: String,
+        reason: String,
+    },
+    KeyNotFound(String),
+    ValueTooLarge {
+        size: usize,
+        max: usize,
+    },
+    TooDeeplyNested {
+        depth: usize,
+        max: usize,
+    },
+    QuotaExceeded {
+        used: usize,
+        requested: usize,
+        quota: usize,
+    },
+    SchemaViolation {
+        prefix: String,
+        violations: Vec<SchemaViolation>,
+    },
+    Episode(EpisodeError),
+    /// `reject_similar` found a near-duplicate under another key
+    NearDuplicate {
+        key: String,
+        similarity: f64,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::InvalidKey { key, reason } => write!(f, "invalid key \"{}\": {}", key, reason),
+            StoreError::KeyNotFound(key) => write!(f, "key \"{}\" not found", key),
+            StoreError::ValueTooLarge { size, max } => {
+                write!(f, "value is {} bytes, limit is {} bytes", size, max)
+            }
+            StoreError::TooDeeplyNested { depth, max } => {
+                write!(f, "value is nested {} levels deep, limit is {}", depth, max)
+            }
+            StoreError::QuotaExceeded { used, requested, quota } => write!(
+                f,
+                "quota exceeded: {} bytes used, {} requested, quota is {} bytes",
+                used, requested, quota
+            ),
+            StoreError::SchemaViolation { prefix, violations } => {
+                write!(f, "value violates schema for \"{}\"", prefix)?;
+                for violation in violations {
+                    write!(f, "; {}", violation)?;
+                }
+                Ok(())
+            }
+            StoreError::Episode(e) => write!(f, "{}", e),
+            StoreError::NearDuplicate { key, similarity } => {
+                write!(f, "similar entry already exists: key {} (similarity {:.2})", key, similarity)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<EpisodeError> for StoreError {
+    fn from(e: EpisodeError) -> Self {
+        StoreError::Episode(e)
+    }
+}
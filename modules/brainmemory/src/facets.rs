@@ -0,0 +1,137 @@
+// SimplyKI BrainMemory - Search Facets
+// Erstellt: 2026-10-19 19:45:00 CEST
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::search::Tier;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateInterval {
+    /// `2026-10-19`
+    #[default]
+    Day,
+    /// ISO week, `2026-W42`
+    Week,
+    /// `2026-10`
+    Month,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FacetOptions {
+    /// Most common values kept per facet; dates keep the newest buckets
+    pub size: usize,
+    pub date_interval: DateInterval,
+}
+
+impl Default for FacetOptions {
+    fn default() -> Self {
+        Self { size: 10, date_interval: DateInterval::Day }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Match counts across all pages of a search
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Facets {
+    /// First key segment before `/`; empty for keys without one
+    pub namespace: Vec<FacetCount>,
+    pub tag: Vec<FacetCount>,
+    pub tier: Vec<FacetCount>,
+    /// Creation date in UTC, bucketed by `FacetOptions::date_interval`
+    pub created: Vec<FacetCount>,
+}
+
+#[derive(Debug, Default)]
+pub struct FacetCounter {
+    namespace: HashMap<String, usize>,
+    tag: HashMap<String, usize>,
+    tier: HashMap<String, usize>,
+    created: HashMap<String, usize>,
+}
+
+impl FacetCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, key: &str, tags: &[String], tier: Tier, created: DateTime<Utc>, interval: DateInterval) {
+        let namespace = key.split_once('/').map_or("", |(ns, _)| ns);
+        *self.namespace.entry(namespace.to_string()).or_insert(0) += 1;
+        for tag in tags {
+            *self.tag.entry(tag.clone()).or_insert(0) += 1;
+        }
+        let tier = match tier {
+            Tier::Working => "working",
+            Tier::LongTerm => "long_term",
+        };
+        *self.tier.entry(tier.to_string()).or_insert(0) += 1;
+        *self.created.entry(bucket(created, interval)).or_insert(0) += 1;
+    }
+
+    pub fn finish(self, options: &FacetOptions) -> Facets {
+        let by_count = |counts: HashMap<String, usize>| {
+            let mut counts = into_counts(counts);
+            counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            counts.truncate(options.size);
+            counts
+        };
+        let mut created = into_counts(self.created);
+        created.sort_by(|a, b| b.value.cmp(&a.value));
+        created.truncate(options.size);
+
+        Facets {
+            namespace: by_count(self.namespace),
+            tag: by_count(self.tag),
+            tier: by_count(self.tier),
+            created,
+        }
+    }
+}
+
+fn into_counts(counts: HashMap<String, usize>) -> Vec<FacetCount> {
+    counts.into_iter().map(|(value, count)| FacetCount { value, count }).collect()
+}
+
+fn bucket(time: DateTime<Utc>, interval: DateInterval) -> String {
+    let format = match interval {
+        DateInterval::Day => "%Y-%m-%d",
+        DateInterval::Week => "%G-W%V",
+        DateInterval::Month => "%Y-%m",
+    };
+    time.format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_facet_counts() {
+        let day = |d: u32| DateTime::parse_from_rfc3339(&format!("2026-10-{:02}T12:00:00Z", d)).unwrap().to_utc();
+        let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let mut counter = FacetCounter::new();
+        counter.add("notes/a", &tags(&["rust"]), Tier::Working, day(19), DateInterval::Day);
+        counter.add("notes/b", &tags(&["rust", "db"]), Tier::LongTerm, day(12), DateInterval::Day);
+        counter.add("plain", &[], Tier::Working, day(19), DateInterval::Day);
+        let facets = counter.finish(&FacetOptions { size: 2, ..Default::default() });
+
+        let count = |value: &str, count| FacetCount { value: value.to_string(), count };
+        assert_eq!(facets.namespace, vec![count("notes", 2), count("", 1)]);
+        assert_eq!(facets.tag, vec![count("rust", 2), count("db", 1)]);
+        assert_eq!(facets.tier, vec![count("working", 2), count("long_term", 1)]);
+        assert_eq!(facets.created, vec![count("2026-10-19", 2), count("2026-10-12", 1)]);
+
+        assert_eq!(bucket(day(19), DateInterval::Week), "2026-W43");
+        assert_eq!(bucket(day(19), DateInterval::Month), "2026-10");
+    }
+}
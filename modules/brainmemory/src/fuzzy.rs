@@ -0,0 +1,101 @@
+// SimplyKI BrainMemory - Fuzzy Term Matching
+// Erstellt: 2026-10-19 14:40:00 CEST
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FuzzyOptions {
+    /// Upper bound on edits (insert, delete, substitute, swap neighbours);
+    /// short terms allow fewer, see `allowed_distance`
+    pub max_distance: usize,
+    /// Also match indexed terms that start with a query term
+    pub prefix: bool,
+}
+
+impl Default for FuzzyOptions {
+    fn default() -> Self {
+        Self {
+            max_distance: 2,
+            prefix: true,
+        }
+    }
+}
+
+impl FuzzyOptions {
+    /// Edits allowed for a term: none up to 3 characters, one up to 6
+    pub fn allowed_distance(&self, term: &str) -> usize {
+        let len = term.chars().count();
+        let by_length = match len {
+            0..=3 => 0,
+            4..=6 => 1,
+            _ => 2,
+        };
+        by_length.min(self.max_distance)
+    }
+}
+
+/// Optimal string alignment distance, or `None` if it exceeds `max`
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let width = b.len() + 1;
+    let mut prev2: Vec<usize> = vec![0; width];
+    let mut prev: Vec<usize> = (0..width).collect();
+    let mut curr: Vec<usize> = vec![0; width];
+
+    for i in 1..=a.len() {
+        curr[0] = i;
+        let mut row_min = curr[0];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                curr[j] = curr[j].min(prev2[j - 2] + 1);
+            }
+            row_min = row_min.min(curr[j]);
+        }
+        // Every later row is at least this row's minimum
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    let distance = prev[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+/// Weight of a fuzzy match relative to an exact one
+pub fn similarity(term: &str, distance: usize) -> f64 {
+    1.0 - distance as f64 / (term.chars().count() as f64 + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("speicher", "speicher", 2), Some(0));
+        assert_eq!(edit_distance("speicher", "speichre", 2), Some(1));
+        assert_eq!(edit_distance("server", "servr", 2), Some(1));
+        assert_eq!(edit_distance("größe", "grösse", 2), Some(2));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance("a", "abcdef", 2), None);
+    }
+
+    #[test]
+    fn test_allowed_distance() {
+        let options = FuzzyOptions::default();
+        assert_eq!(options.allowed_distance("rs"), 0);
+        assert_eq!(options.allowed_distance("tokio"), 1);
+        assert_eq!(options.allowed_distance("arbeitsspeicher"), 2);
+        assert_eq!(FuzzyOptions { max_distance: 1, prefix: false }.allowed_distance("arbeitsspeicher"), 1);
+    }
+}
@@ -0,0 +1,343 @@
+// SimplyKI BrainMemory - Inverted Index over Value Content
+// Erstellt: 2026-10-19 13:10:00 CEST
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::fuzzy::{edit_distance, similarity, FuzzyOptions};
+use crate::search::{Bm25Params, TermScore};
+use crate::analysis::Analyzer;
+use crate::text::collect_strings;
+
+/// term -> key -> term frequency, maintained incrementally per key.
+/// A document is the key itself plus the string content of its value,
+/// turned into terms by the `Analyzer`.
+#[derive(Debug, Clone, Default)]
+pub struct InvertedIndex {
+    analyzer: Analyzer,
+    postings: BTreeMap<String, HashMap<String, u32>>,
+    docs: HashMap<String, DocInfo>,
+    /// Documents containing a term as written, for completions
+    surface_docs: HashMap<String, usize>,
+    total_len: u64,
+}
+
+#[derive(Debug, Clone)]
+struct DocInfo {
+    terms: Vec<String>,
+    surface: Vec<String>,
+    len: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub terms: usize,
+    pub documents: usize,
+}
+
+impl InvertedIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_analyzer(analyzer: Analyzer) -> Self {
+        Self { analyzer, ..Self::default() }
+    }
+
+    pub fn analyzer(&self) -> &Analyzer {
+        &self.analyzer
+    }
+
+    /// Index `key` and the string content of `value`, replacing what was
+    /// indexed for `key` before
+    pub fn insert(&mut self, key: &str, value: &Value) {
+        self.remove(key);
+
+        let mut strings = vec![key];
+        collect_strings(value, &mut strings);
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        let mut surface: Vec<String> = Vec::new();
+        let mut len = 0;
+        for (term, is_surface) in self.analyzer.index_terms(key, &strings) {
+            if is_surface && !surface.contains(&term) {
+                surface.push(term.clone());
+            }
+            *frequencies.entry(term).or_insert(0) += 1;
+            len += 1;
+        }
+        if frequencies.is_empty() {
+            return;
+        }
+
+        let mut terms = Vec::with_capacity(frequencies.len());
+        for (term, tf) in frequencies {
+            self.postings.entry(term.clone()).or_default().insert(key.to_string(), tf);
+            terms.push(term);
+        }
+        for term in &surface {
+            *self.surface_docs.entry(term.clone()).or_insert(0) += 1;
+        }
+        self.total_len += len as u64;
+        self.docs.insert(key.to_string(), DocInfo { terms, surface, len });
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        let Some(doc) = self.docs.remove(key) else {
+            return;
+        };
+        self.total_len -= doc.len as u64;
+        for term in doc.surface {
+            if let Some(count) = self.surface_docs.get_mut(&term) {
+                *count -= 1;
+                if *count == 0 {
+                    self.surface_docs.remove(&term);
+                }
+            }
+        }
+        for term in doc.terms {
+            if let Some(docs) = self.postings.get_mut(&term) {
+                docs.remove(key);
+                if docs.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Keys containing `term`, with its frequency in each
+    pub fn postings(&self, term: &str) -> Option<&HashMap<String, u32>> {
+        self.postings.get(term)
+    }
+
+    /// BM25 where each query term stands for a group of weighted indexed
+    /// terms (e.g. its fuzzy variants). A key scores the best variant of
+    /// each group, so near-duplicate variants do not add up.
+    pub fn bm25_expanded(&self, groups: &[Vec<(String, f64)>], params: &Bm25Params) -> HashMap<&str, f64> {
+        let mut scores: HashMap<&str, f64> = HashMap::new();
+        if self.docs.is_empty() {
+            return scores;
+        }
+
+        let n = self.docs.len() as f64;
+        let avg_len = self.total_len as f64 / n;
+        for group in groups {
+            let mut best: HashMap<&str, f64> = HashMap::new();
+            for (term, weight) in group {
+                let Some(docs) = self.postings.get(term) else {
+                    continue;
+                };
+
+                let idf = idf(n, docs.len() as f64);
+                for (key, &tf) in docs {
+                    let score = weight * idf * saturate(tf, self.docs[key].len, avg_len, params);
+                    let entry = best.entry(key.as_str()).or_insert(0.0);
+                    *entry = entry.max(score);
+                }
+            }
+            for (key, score) in best {
+                *scores.entry(key).or_insert(0.0) += score;
+            }
+        }
+        scores
+    }
+
+    /// Per query term group, the variant that contributed to the BM25
+    /// score of `key`; groups without a match in `key` are left out
+    pub fn explain(&self, key: &str, groups: &[Vec<(String, f64)>], params: &Bm25Params) -> Vec<TermScore> {
+        let Some(doc) = self.docs.get(key) else {
+            return Vec::new();
+        };
+        let n = self.docs.len() as f64;
+        let avg_len = self.total_len as f64 / n;
+
+        let mut matched = Vec::new();
+        for group in groups {
+            let best = group
+                .iter()
+                .filter_map(|(term, weight)| {
+                    let docs = self.postings.get(term)?;
+                    let tf = *docs.get(key)?;
+                    let score = weight * idf(n, docs.len() as f64) * saturate(tf, doc.len, avg_len, params);
+                    Some(TermScore { term: term.clone(), frequency: tf, score })
+                })
+                .max_by(|a, b| a.score.total_cmp(&b.score));
+            matched.extend(best);
+        }
+        matched
+    }
+
+    /// Indexed terms close to `term`, weighted by similarity; the exact
+    /// term (if indexed) has weight 1.0
+    pub fn expand(&self, term: &str, options: &FuzzyOptions) -> Vec<(String, f64)> {
+        let max = options.allowed_distance(term);
+        let mut variants: Vec<(String, f64)> = Vec::new();
+        for candidate in self.postings.keys() {
+            if let Some(distance) = edit_distance(term, candidate, max) {
+                variants.push((candidate.clone(), similarity(term, distance)));
+            }
+        }
+
+        if options.prefix {
+            for candidate in self.terms_with_prefix(term) {
+                if candidate != term && !variants.iter().any(|(v, _)| v == candidate) {
+                    let extra = candidate.chars().count() - term.chars().count();
+                    variants.push((candidate.to_string(), similarity(candidate, extra)));
+                }
+            }
+        }
+        variants
+    }
+
+    fn terms_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.postings
+            .range(prefix.to_string()..)
+            .map(|(term, _)| term)
+            .take_while(move |term| term.starts_with(prefix))
+    }
+
+    /// Completions for a partial term, most common first; stems and
+    /// compound parts are not offered
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<(String, usize)> {
+        let mut terms: Vec<(String, usize)> = self
+            .terms_with_prefix(prefix)
+            .filter_map(|term| self.surface_docs.get(term).map(|&count| (term.clone(), count)))
+            .collect();
+        terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        terms.truncate(limit);
+        terms
+    }
+
+    /// Most distinctive words of a value against the indexed corpus by
+    /// TF-IDF, best first. Stopwords, numbers and words shorter than three
+    /// characters are skipped; stems and compound parts are never returned.
+    pub fn keywords(&self, key: &str, value: &Value, limit: usize) -> Vec<(String, f64)> {
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for (term, is_surface) in self.analyzer.index_terms(key, &strings) {
+            if is_surface && term.chars().count() >= 3 && !term.chars().all(|c| c.is_numeric()) {
+                *frequencies.entry(term).or_insert(0) += 1;
+            }
+        }
+
+        let docs = self.docs.len().max(1) as f64;
+        let mut keywords: Vec<(String, f64)> = frequencies
+            .into_iter()
+            .map(|(term, tf)| {
+                let df = self.surface_docs.get(&term).copied().unwrap_or(0) as f64;
+                let score = tf as f64 * idf(docs, df);
+                (term, score)
+            })
+            .collect();
+        keywords.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        keywords.truncate(limit);
+        keywords
+    }
+
+    pub fn info(&self) -> IndexInfo {
+        IndexInfo {
+            terms: self.postings.len(),
+            documents: self.docs.len(),
+        }
+    }
+}
+
+fn idf(docs: f64, df: f64) -> f64 {
+    (1.0 + (docs - df + 0.5) / (df + 0.5)).ln()
+}
+
+/// Term frequency part of BM25, normalized by document length
+fn saturate(tf: u32, len: u32, avg_len: f64, params: &Bm25Params) -> f64 {
+    let tf = tf as f64;
+    let norm = params.k1 * (1.0 - params.b + params.b * len as f64 / avg_len);
+    tf * (params.k1 + 1.0) / (tf + norm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// One group per query term, each matching only the term itself
+    fn exact(terms: &[&str]) -> Vec<Vec<(String, f64)>> {
+        terms.iter().map(|t| vec![(t.to_string(), 1.0)]).collect()
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let mut index = InvertedIndex::new();
+        index.insert("k", &json!({"text": "Rust memory server", "tags": ["rust"]}));
+        index.insert("b", &json!("shell script"));
+
+        assert_eq!(index.postings("rust").unwrap()["k"], 2);
+        assert_eq!(index.postings("k").unwrap()["k"], 1);
+
+        index.insert("k", &json!("only shell now"));
+        assert!(index.postings("rust").is_none());
+        assert_eq!(index.postings("shell").unwrap().len(), 2);
+
+        index.remove("b");
+        index.remove("k");
+        assert_eq!(index.info().terms, 0);
+        assert_eq!(index.info().documents, 0);
+        assert_eq!(index.total_len, 0);
+    }
+
+    #[test]
+    fn test_keywords_prefer_rare_words() {
+        let mut index = InvertedIndex::new();
+        index.insert("d1", &json!("the tokio runtime drives the server"));
+        index.insert("d2", &json!("the server uses warp on tokio"));
+        let value = json!({"text": "warp server with warp filters", "port": "5000"});
+        index.insert("d3", &value);
+
+        let keywords: Vec<String> = index.keywords("d3", &value, 2).into_iter().map(|(k, _)| k).collect();
+        // One unique word outweighs two shared ones
+        assert_eq!(keywords, vec!["filters", "warp"]);
+    }
+
+    #[test]
+    fn test_bm25_prefers_more_and_rarer_terms() {
+        let mut index = InvertedIndex::new();
+        index.insert("d1", &json!("tokio runtime config"));
+        index.insert("d2", &json!("tokio warp server"));
+        index.insert("d3", &json!("tokio"));
+        index.insert("d4", &json!("unrelated words here"));
+        let params = Bm25Params::default();
+
+        let scores = index.bm25_expanded(&exact(&["tokio", "warp"]), &params);
+        assert!(scores["d2"] > scores["d1"]);
+        assert!(!scores.contains_key("d4"));
+
+        // "warp" is rarer than "tokio", so it weighs more
+        let tokio = index.bm25_expanded(&exact(&["tokio"]), &params);
+        let warp = index.bm25_expanded(&exact(&["warp"]), &params);
+        assert!(warp["d2"] > tokio["d2"]);
+
+        // A group scores its best variant, not the sum of its variants
+        let both = vec![vec![("tokio".to_string(), 1.0), ("warp".to_string(), 1.0)]];
+        assert_eq!(index.bm25_expanded(&both, &params)["d2"], warp["d2"]);
+    }
+
+    #[test]
+    fn test_fuzzy_expansion_and_completion() {
+        let mut index = InvertedIndex::new();
+        index.insert("d1", &json!("Arbeitsspeicher voll"));
+        index.insert("d2", &json!("speicher server serverless"));
+
+        let options = FuzzyOptions::default();
+        let variants = index.expand("servr", &options);
+        assert!(variants.iter().any(|(t, w)| t == "server" && *w < 1.0));
+        let variants = index.expand("arbeitspeicher", &options);
+        assert!(variants.iter().any(|(t, _)| t == "arbeitsspeicher"));
+
+        // Prefix matches, but a typo group only counts once per key
+        let groups = vec![index.expand("serv", &options)];
+        let scores = index.bm25_expanded(&groups, &Bm25Params::default());
+        assert_eq!(scores.len(), 1);
+
+        assert_eq!(index.complete("ser", 5), vec![("server".to_string(), 1), ("serverless".to_string(), 1)]);
+        assert!(index.complete("xyz", 5).is_empty());
+    }
+}
@@ -1,10 +1,53 @@
 // SimplyKI BrainMemory - Library Interface
 // Erstellt: 2025-07-24 16:48:00 CEST
 
+pub mod analysis;
+pub mod ann;
+pub mod consolidation;
+pub mod content;
+pub mod context;
+pub mod embedding;
+pub mod entity;
+pub mod episode;
+pub mod error;
+pub mod facets;
+pub mod fuzzy;
+pub mod index;
+pub mod limits;
 pub mod memory;
+pub mod minhash;
+pub mod pattern;
+pub mod query;
+pub mod remote_embedder;
+pub mod schema;
+pub mod search;
 pub mod server;
+pub mod snippet;
+pub mod text;
+pub mod watch;
 
-pub use memory::{BrainMemory, MemoryStats};
+pub use analysis::{Analyzer, AnalyzerConfig, Language};
+pub use ann::HnswParams;
+pub use consolidation::{summarize_episode, Consolidator, ConsolidatorConfig, HeuristicConsolidator, SemanticFact, SummaryConsolidator};
+pub use content::{ContentHash, DedupInfo};
+pub use context::{ContextItem, ContextOptions, ContextPack, DropReason, DroppedItem};
+pub use embedding::{EmbedError, Embedder, HashingEmbedder};
+pub use entity::{Entity, EntityCount, EntityKind, EntityMention};
+pub use episode::{Episode, EpisodeError, EpisodeEvent, EpisodeId, EpisodeQuery};
+pub use error::StoreError;
+pub use facets::{DateInterval, FacetCount, FacetOptions, Facets};
+pub use fuzzy::FuzzyOptions;
+pub use index::IndexInfo;
+pub use limits::StoreLimits;
+pub use memory::{AutoTagConfig, BrainMemory, ExportedEntry, MemoryStats, MentionedEntry, StoreOptions};
+pub use minhash::DuplicateCluster;
+pub use pattern::{KeyMatcher, KeyPattern, PatternError};
+pub use query::{ParsedQuery, Query, QueryError};
+pub use remote_embedder::{RemoteEmbedder, RemoteEmbedderConfig};
+pub use schema::{SchemaRegistry, SchemaViolation};
+pub use search::{Bm25Params, Cursor, CursorError, Explanation, Fusion, Ranking, SearchHit, SearchMode, SearchOptions, SearchPage, SemanticOptions, Suggestion, SuggestionKind, TermScore, Tier};
+pub use snippet::{Highlight, Snippet, SnippetOptions};
+pub use watch::{Change, Watch, WatchEvent, WatchId};
 
 /// Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
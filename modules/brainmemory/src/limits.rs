@@ -0,0 +1,137 @@
+// SimplyKI BrainMemory - Store Limits
+// Erstellt: 2026-10-19 12:30:00 CEST
+
+use std::io;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::StoreError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreLimits {
+    pub max_key_len: usize,
+    /// Serialized JSON size of a single value
+    pub max_value_bytes: usize,
+    pub max_depth: usize,
+    /// Bytes held across both tiers after deduplication
+    pub max_total_bytes: usize,
+}
+
+impl Default for StoreLimits {
+    fn default() -> Self {
+        Self {
+            max_key_len: 512,
+            max_value_bytes: 1024 * 1024, // 1MB
+            max_depth: 32,
+            max_total_bytes: 256 * 1024 * 1024, // 256MB
+        }
+    }
+}
+
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialized size of a value without allocating the string
+pub fn value_size(value: &Value) -> usize {
+    let mut counter = ByteCounter(0);
+    // Writing a Value into an infallible writer cannot fail
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// Nesting depth; scalars are 0, `[]` and `{}` are 1
+pub fn value_depth(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => 1 + items.iter().map(value_depth).max().unwrap_or(0),
+        Value::Object(map) => 1 + map.values().map(value_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+impl StoreLimits {
+    pub fn check_key(&self, key: &str) -> Result<(), StoreError> {
+        let reason = if key.is_empty() {
+            "key must not be empty".to_string()
+        } else if key.len() > self.max_key_len {
+            format!("key is longer than {} bytes", self.max_key_len)
+        } else if key.chars().any(char::is_control) {
+            "key contains control characters".to_string()
+        } else {
+            return Ok(());
+        };
+        Err(StoreError::InvalidKey {
+            key: key.chars().take(64).collect(),
+            reason,
+        })
+    }
+
+    /// Check size and depth of `value`. Returns the serialized size.
+    pub fn check_value(&self, value: &Value) -> Result<usize, StoreError> {
+        let depth = value_depth(value);
+        if depth > self.max_depth {
+            return Err(StoreError::TooDeeplyNested { depth, max: self.max_depth });
+        }
+
+        let size = value_size(value);
+        if size > self.max_value_bytes {
+            return Err(StoreError::ValueTooLarge { size, max: self.max_value_bytes });
+        }
+        Ok(size)
+    }
+
+    /// Check that a write adding `added_bytes` to the `used_bytes` already
+    /// stored stays within the quota
+    pub fn check_quota(&self, used_bytes: usize, added_bytes: usize) -> Result<(), StoreError> {
+        if added_bytes > 0 && used_bytes + added_bytes > self.max_total_bytes {
+            return Err(StoreError::QuotaExceeded {
+                used: used_bytes,
+                requested: added_bytes,
+                quota: self.max_total_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_limits() {
+        let limits = StoreLimits {
+            max_key_len: 8,
+            max_value_bytes: 32,
+            max_depth: 2,
+            max_total_bytes: 100,
+        };
+
+        assert!(limits.check_key("ok").is_ok());
+        assert!(matches!(limits.check_key(""), Err(StoreError::InvalidKey { .. })));
+        assert!(matches!(limits.check_key("much_too_long"), Err(StoreError::InvalidKey { .. })));
+        assert!(matches!(limits.check_key("a\nb"), Err(StoreError::InvalidKey { .. })));
+
+        assert_eq!(limits.check_value(&json!({"a": [1]})), Ok(9));
+        assert!(matches!(
+            limits.check_value(&json!({"a": [[1]]})),
+            Err(StoreError::TooDeeplyNested { depth: 3, max: 2 })
+        ));
+        assert!(matches!(
+            limits.check_value(&json!("x".repeat(40))),
+            Err(StoreError::ValueTooLarge { size: 42, max: 32 })
+        ));
+        assert!(matches!(limits.check_quota(98, 3), Err(StoreError::QuotaExceeded { .. })));
+        assert!(limits.check_quota(120, 0).is_ok());
+    }
+}
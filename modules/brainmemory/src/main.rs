@@ -4,8 +4,7 @@
 use std::env;
 use std::process;
 
-mod memory;
-mod server;
+use brainmemory::{memory, server};
 
 #[tokio::main]
 async fn main() {
@@ -70,7 +69,7 @@ fn run_benchmark() {
         brain.store(&format!("key_{}", i), serde_json::json!({
             "id": i,
             "data": format!("test data {}", i)
-        }));
+        })).expect("benchmark keys have no schema");
     }
     let rust_write = start.elapsed().as_millis();
     println!("   Rust:  {}ms", rust_write);
@@ -129,7 +128,7 @@ fn run_demo() {
         input.clear();
         io::stdin().read_line(&mut input).unwrap();
         
-        let parts: Vec<&str> = input.trim().split_whitespace().collect();
+        let parts: Vec<&str> = input.split_whitespace().collect();
         if parts.is_empty() {
             continue;
         }
@@ -139,8 +138,10 @@ fn run_demo() {
                 if parts.len() >= 3 {
                     let key = parts[1];
                     let value = parts[2..].join(" ");
-                    brain.store(key, serde_json::json!(value));
-                    println!("✓ Stored: {} = {}", key, value);
+                    match brain.store(key, serde_json::json!(value)) {
+                        Ok(()) => println!("✓ Stored: {} = {}", key, value),
+                        Err(e) => println!("✗ Not stored: {}", e),
+                    }
                 } else {
                     println!("Usage: store <key> <value>");
                 }
@@ -0,0 +1,219 @@
+// SimplyKI BrainMemory - Near-Duplicate Detection
+// Erstellt: 2026-10-19 21:30:00 CEST
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::text::{collect_strings, fnv1a, tokenize};
+
+/// Hash functions per signature
+const PERMUTATIONS: usize = 64;
+
+/// Locality-sensitive hashing: signatures agreeing on all rows of any band
+/// become candidates. 16 bands of 4 rows find pairs above a similarity of
+/// about 0.5 reliably; lower thresholds miss some.
+const BANDS: usize = 16;
+const ROWS: usize = PERMUTATIONS / BANDS;
+
+/// MinHash of an entry's words; signatures agree in the share of
+/// positions that estimates the Jaccard similarity of the word sets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature([u32; PERMUTATIONS]);
+
+impl Signature {
+    /// `None` for values without any words
+    pub fn of(value: &Value) -> Option<Self> {
+        let mut strings = Vec::new();
+        collect_strings(value, &mut strings);
+        let words: HashSet<String> = strings.iter().flat_map(|s| tokenize(s)).collect();
+        if words.is_empty() {
+            return None;
+        }
+
+        let mut mins = [u32::MAX; PERMUTATIONS];
+        for word in &words {
+            let base = fnv1a(word.as_bytes());
+            for (i, min) in mins.iter_mut().enumerate() {
+                let hash = mix(base ^ mix(i as u64 + 1)) as u32;
+                *min = (*min).min(hash);
+            }
+        }
+        Some(Self(mins))
+    }
+
+    pub fn similarity(&self, other: &Signature) -> f64 {
+        let same = self.0.iter().zip(&other.0).filter(|(a, b)| a == b).count();
+        same as f64 / PERMUTATIONS as f64
+    }
+
+    fn band(&self, band: usize) -> u64 {
+        let rows = &self.0[band * ROWS..(band + 1) * ROWS];
+        let bytes: Vec<u8> = rows.iter().flat_map(|r| r.to_le_bytes()).collect();
+        fnv1a(&bytes)
+    }
+}
+
+/// splitmix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Keys whose values were found to be near duplicates of each other
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateCluster {
+    pub keys: Vec<String>,
+    /// Lowest estimated similarity among the pairs linking the cluster
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateIndex {
+    signatures: HashMap<String, Signature>,
+    buckets: HashMap<(usize, u64), Vec<String>>,
+}
+
+impl DuplicateIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: &Value) {
+        self.remove(key);
+        let Some(signature) = Signature::of(value) else {
+            return;
+        };
+        for band in 0..BANDS {
+            self.buckets.entry((band, signature.band(band))).or_default().push(key.to_string());
+        }
+        self.signatures.insert(key.to_string(), signature);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        let Some(signature) = self.signatures.remove(key) else {
+            return;
+        };
+        for band in 0..BANDS {
+            let bucket = (band, signature.band(band));
+            if let Some(keys) = self.buckets.get_mut(&bucket) {
+                keys.retain(|k| k != key);
+                if keys.is_empty() {
+                    self.buckets.remove(&bucket);
+                }
+            }
+        }
+    }
+
+    /// Indexed keys at least `threshold` similar to `value`, most similar first
+    pub fn similar(&self, value: &Value, threshold: f64) -> Vec<(String, f64)> {
+        let Some(signature) = Signature::of(value) else {
+            return Vec::new();
+        };
+        let mut similar: Vec<(String, f64)> = self
+            .candidates(&signature)
+            .into_iter()
+            .filter_map(|key| {
+                let similarity = signature.similarity(&self.signatures[key]);
+                (similarity >= threshold).then(|| (key.clone(), similarity))
+            })
+            .collect();
+        similar.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        similar
+    }
+
+    /// Groups of keys linked by pairs at least `threshold` similar, largest first
+    pub fn clusters(&self, threshold: f64) -> Vec<DuplicateCluster> {
+        let mut parent: HashMap<&String, &String> = HashMap::new();
+        let mut weakest: HashMap<(&String, &String), f64> = HashMap::new();
+
+        for (key, signature) in &self.signatures {
+            for other in self.candidates(signature) {
+                if other <= key {
+                    continue;
+                }
+                let similarity = signature.similarity(&self.signatures[other]);
+                if similarity >= threshold {
+                    weakest.insert((key, other), similarity);
+                    let (a, b) = (find(&mut parent, key), find(&mut parent, other));
+                    if a != b {
+                        parent.insert(a.max(b), a.min(b));
+                    }
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<&String, DuplicateCluster> = BTreeMap::new();
+        let mut members: HashSet<&String> = HashSet::new();
+        for (&(key, other), similarity) in &weakest {
+            let root = find(&mut parent, key);
+            let cluster = groups.entry(root).or_insert(DuplicateCluster { keys: Vec::new(), similarity: 1.0 });
+            cluster.similarity = cluster.similarity.min(*similarity);
+            members.extend([key, other]);
+        }
+        for key in members {
+            let root = find(&mut parent, key);
+            groups.get_mut(root).expect("every member has a linked root").keys.push(key.clone());
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = groups.into_values().collect();
+        for cluster in &mut clusters {
+            cluster.keys.sort();
+        }
+        clusters.sort_by(|a, b| b.keys.len().cmp(&a.keys.len()).then_with(|| a.keys.cmp(&b.keys)));
+        clusters
+    }
+
+    fn candidates(&self, signature: &Signature) -> HashSet<&String> {
+        (0..BANDS)
+            .filter_map(|band| self.buckets.get(&(band, signature.band(band))))
+            .flatten()
+            .collect()
+    }
+}
+
+/// Union-find root with path halving; keys without a parent are roots
+fn find<'a>(parent: &mut HashMap<&'a String, &'a String>, mut key: &'a String) -> &'a String {
+    while let Some(&next) = parent.get(key) {
+        if let Some(&grand) = parent.get(next) {
+            parent.insert(key, grand);
+        }
+        key = next;
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_signature_similarity() {
+        let a = Signature::of(&json!("the brainmemory server listens on port 5000 by default")).unwrap();
+        let b = Signature::of(&json!({"fact": "by default the brainmemory server listens on port 5000"})).unwrap();
+        let c = Signature::of(&json!("pancakes need flour eggs and milk")).unwrap();
+        assert_eq!(a.similarity(&b), 1.0);
+        assert!(a.similarity(&c) < 0.2);
+        assert!(Signature::of(&json!(42)).is_none());
+    }
+
+    #[test]
+    fn test_clusters() {
+        let mut index = DuplicateIndex::new();
+        index.insert("a", &json!("the brainmemory server listens on port 5000 by default"));
+        index.insert("b", &json!("the brainmemory server listens on port 5000 by default now"));
+        index.insert("c", &json!("brainmemory server listens on port 5000 by default"));
+        index.insert("d", &json!("pancakes need flour eggs and milk"));
+
+        let clusters = index.clusters(0.7);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].keys, vec!["a", "b", "c"]);
+        assert!(clusters[0].similarity >= 0.7);
+
+        assert_eq!(index.similar(&json!("the brainmemory server listens on port 5000 by default"), 0.9)[0].0, "a");
+        index.remove("a");
+        assert!(index.similar(&json!("pancakes need milk flour eggs and"), 0.9).iter().all(|(k, _)| k == "d"));
+    }
+}
@@ -0,0 +1,152 @@
+// SimplyKI BrainMemory - Key Patterns
+// Erstellt: 2026-10-19 20:55:00 CEST
+
+use std::fmt;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+/// Longest pattern accepted, in characters
+const MAX_PATTERN_CHARS: usize = 512;
+
+/// Upper bound for the compiled program and its lazy DFA cache; the regex
+/// engine matches in linear time, so this bounds the remaining cost
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Deepest nesting of groups and repetitions
+const REGEX_NEST_LIMIT: u32 = 32;
+
+/// Pattern over whole keys; both kinds are anchored at start and end
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPattern {
+    /// `*` and `?` stay within one `/` segment, `**` spans segments,
+    /// `[abc]` and `[!abc]` are character classes, `\` escapes
+    Glob(String),
+    /// Rust `regex` syntax
+    Regex(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern: {}", self.message)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl PatternError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+/// A compiled `KeyPattern`
+#[derive(Debug, Clone)]
+pub struct KeyMatcher {
+    regex: Regex,
+}
+
+impl KeyMatcher {
+    pub fn is_match(&self, key: &str) -> bool {
+        self.regex.is_match(key)
+    }
+}
+
+impl KeyPattern {
+    pub fn compile(&self) -> Result<KeyMatcher, PatternError> {
+        let (KeyPattern::Glob(pattern) | KeyPattern::Regex(pattern)) = self;
+        if pattern.chars().count() > MAX_PATTERN_CHARS {
+            return Err(PatternError::new(format!("longer than {} characters", MAX_PATTERN_CHARS)));
+        }
+        let source = match self {
+            KeyPattern::Glob(glob) => glob_to_regex(glob)?,
+            KeyPattern::Regex(regex) => regex.clone(),
+        };
+        let regex = RegexBuilder::new(&format!("^(?:{})$", source))
+            .size_limit(REGEX_SIZE_LIMIT)
+            .dfa_size_limit(REGEX_SIZE_LIMIT)
+            .nest_limit(REGEX_NEST_LIMIT)
+            .build()
+            .map_err(|e| PatternError::new(e.to_string()))?;
+        Ok(KeyMatcher { regex })
+    }
+}
+
+fn glob_to_regex(glob: &str) -> Result<String, PatternError> {
+    let mut out = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                out.push_str(".*");
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => {
+                out.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    out.push('^');
+                }
+                let mut empty = true;
+                loop {
+                    match chars.next() {
+                        Some(']') if !empty => break,
+                        Some(c) => {
+                            if matches!(c, '\\' | '[' | ']' | '&' | '~' | '^') {
+                                out.push('\\');
+                            }
+                            out.push(c);
+                            empty = false;
+                        }
+                        None => return Err(PatternError::new("unterminated '[' in glob")),
+                    }
+                }
+                out.push(']');
+            }
+            '\\' => match chars.next() {
+                Some(escaped) => out.push_str(&regex::escape(&escaped.to_string())),
+                None => return Err(PatternError::new("trailing '\\' in glob")),
+            },
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob() {
+        let summary = KeyPattern::Glob("session/*/summary".to_string()).compile().unwrap();
+        assert!(summary.is_match("session/42/summary"));
+        assert!(!summary.is_match("session/42/x/summary"));
+        assert!(!summary.is_match("session/42/summary.bak"));
+
+        let deep = KeyPattern::Glob("session/**".to_string()).compile().unwrap();
+        assert!(deep.is_match("session/42/x/summary"));
+
+        let class = KeyPattern::Glob("note[!0-9].md".to_string()).compile().unwrap();
+        assert!(class.is_match("notes.md") && !class.is_match("note1.md"));
+        assert!(KeyPattern::Glob("a[b".to_string()).compile().is_err());
+    }
+
+    #[test]
+    fn test_regex_is_anchored_and_limited() {
+        let digits = KeyPattern::Regex(r"session/\d+".to_string()).compile().unwrap();
+        assert!(digits.is_match("session/7"));
+        assert!(!digits.is_match("old/session/7"));
+
+        assert!(KeyPattern::Regex("(a".to_string()).compile().is_err());
+        assert!(KeyPattern::Regex("a{1000}{1000}".to_string()).compile().is_err());
+        assert!(KeyPattern::Regex("a".repeat(MAX_PATTERN_CHARS + 1)).compile().is_err());
+    }
+}
@@ -0,0 +1,608 @@
+// SimplyKI BrainMemory - Structured Query Language
+// Erstellt: 2026-10-19 17:10:00 CEST
+//
+// Grammar (operators are upper case, juxtaposition means AND):
+//
+//   query   := or
+//   or      := and ("OR" and)*
+//   and     := unary ("AND"? unary)*
+//   unary   := ("NOT" | "-") unary | primary
+//   primary := "(" or ")" | "\"phrase\"" | field | term
+//   field   := ns:<prefix> | tag:<tag> | tier:<working|long_term> | entity:<entity>
+//            | project:<name> | /json/pointer=<value> | created<op><date> | updated<op><date>
+//            | sort:[-]<score|key|created|updated>
+//
+// Field values may be quoted, e.g. tag:"needs review". project:<name> matches
+// entries in namespace <name> and entries whose value has "project": "<name>". sort: is a directive,
+// not a filter: it is only allowed at the top level, outside groups, NOT
+// and OR.
+
+use std::fmt;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::search::Tier;
+use crate::text::tokenize;
+
+/// Parse failure; `position` is the character offset into the query
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self { position, message: message.into() }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeField {
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Score,
+    Key,
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Matches every entry
+    All,
+    Term(String),
+    /// Consecutive tokens of the key and string content
+    Phrase(Vec<String>),
+    /// Key is `prefix` or lies below `prefix/`
+    Namespace(String),
+    Tag(String),
+    /// Mentioned entity, see `crate::entity`
+    Entity(String),
+    Tier(Tier),
+    Pointer { pointer: String, value: Value },
+    /// Timestamp within `[from, to)`, or beyond it for `<` and `>`
+    Time { field: TimeField, op: Comparison, from: DateTime<Utc>, to: DateTime<Utc> },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedQuery {
+    pub query: Query,
+    /// Applied in order; ties fall back to score, then key
+    pub sort: Vec<Sort>,
+}
+
+/// What a query is evaluated against
+pub trait Document {
+    fn key(&self) -> &str;
+    fn value(&self) -> &Value;
+    fn tags(&self) -> &[String];
+    /// Tags extracted from the value, matched by `tag:` like `tags`
+    fn auto_tags(&self) -> &[String] {
+        &[]
+    }
+    fn tier(&self) -> Tier;
+    fn created(&self) -> DateTime<Utc>;
+    fn updated(&self) -> DateTime<Utc>;
+    fn has_term(&self, term: &str) -> bool;
+    /// Whether an extracted entity matches, see `EntityIndex::mentions`
+    fn mentions(&self, _entity: &str) -> bool {
+        false
+    }
+
+    fn has_phrase(&self, terms: &[String]) -> bool {
+        let tokens = tokenize(&crate::text::document_text(self.key(), self.value()));
+        tokens.windows(terms.len()).any(|w| w == terms)
+    }
+}
+
+impl Query {
+    pub fn matches(&self, doc: &impl Document) -> bool {
+        match self {
+            Query::All => true,
+            Query::Term(term) => doc.has_term(term),
+            Query::Phrase(terms) => doc.has_phrase(terms),
+            Query::Namespace(ns) => {
+                let key = doc.key();
+                key.strip_prefix(ns.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            }
+            Query::Tag(tag) => doc.tags().iter().chain(doc.auto_tags()).any(|t| t.eq_ignore_ascii_case(tag)),
+            Query::Entity(entity) => doc.mentions(entity),
+            Query::Tier(tier) => doc.tier() == *tier,
+            Query::Pointer { pointer, value } => doc.value().pointer(pointer) == Some(value),
+            Query::Time { field, op, from, to } => {
+                let at = match field {
+                    TimeField::Created => doc.created(),
+                    TimeField::Updated => doc.updated(),
+                };
+                match op {
+                    Comparison::Lt => at < *from,
+                    Comparison::Le => at < *to,
+                    Comparison::Eq => *from <= at && at < *to,
+                    Comparison::Ge => at >= *from,
+                    Comparison::Gt => at >= *to,
+                }
+            }
+            Query::And(items) => items.iter().all(|q| q.matches(doc)),
+            Query::Or(items) => items.iter().any(|q| q.matches(doc)),
+            Query::Not(inner) => !inner.matches(doc),
+        }
+    }
+
+    /// Words outside of negations, used for relevance ranking
+    pub fn positive_terms(&self) -> Vec<String> {
+        let mut terms = Vec::new();
+        self.collect_terms(&mut terms);
+        terms
+    }
+
+    fn collect_terms(&self, out: &mut Vec<String>) {
+        match self {
+            Query::Term(term) => out.push(term.clone()),
+            Query::Phrase(terms) => out.extend(terms.iter().cloned()),
+            Query::And(items) | Query::Or(items) => items.iter().for_each(|q| q.collect_terms(out)),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Phrase(String),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let position = i;
+        let kind = match c {
+            '(' => {
+                i += 1;
+                TokenKind::Open
+            }
+            ')' => {
+                i += 1;
+                TokenKind::Close
+            }
+            '"' => {
+                let end = closing_quote(&chars, i)?;
+                let text: String = chars[i + 1..end].iter().collect();
+                i = end + 1;
+                TokenKind::Phrase(text)
+            }
+            _ => {
+                let mut text = String::new();
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
+                    if chars[i] == '"' {
+                        // Quoted field value, kept with its quotes
+                        let end = closing_quote(&chars, i)?;
+                        text.extend(&chars[i..=end]);
+                        i = end + 1;
+                    } else {
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                }
+                TokenKind::Word(text)
+            }
+        };
+        tokens.push(Token { kind, position });
+    }
+    Ok(tokens)
+}
+
+fn closing_quote(chars: &[char], open: usize) -> Result<usize, QueryError> {
+    chars[open + 1..]
+        .iter()
+        .position(|&c| c == '"')
+        .map(|offset| open + 1 + offset)
+        .ok_or_else(|| QueryError::new(open, "unterminated quote"))
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(text)
+}
+
+pub fn parse(input: &str) -> Result<ParsedQuery, QueryError> {
+    let mut parser = Parser {
+        tokens: lex(input)?,
+        pos: 0,
+        end: input.chars().count(),
+        sort: Vec::new(),
+        sort_positions: Vec::new(),
+        negated: 0,
+        groups: 0,
+    };
+    let query = if parser.tokens.is_empty() { Query::All } else { parser.or()? };
+    if let Some(token) = parser.peek() {
+        let message = match token.kind {
+            TokenKind::Close => "unmatched ')'",
+            _ => "unexpected input",
+        };
+        return Err(QueryError::new(token.position, message));
+    }
+    Ok(ParsedQuery { query, sort: parser.sort })
+}
+
+const SORT_NOT_TOP_LEVEL: &str = "sort is only allowed at the top level, not in groups or OR";
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+    sort: Vec<Sort>,
+    sort_positions: Vec<usize>,
+    /// Depth of enclosing NOTs; sort clauses are not allowed there
+    negated: usize,
+    /// Depth of enclosing parentheses; sort clauses are not allowed there
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_operator(&self, operator: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(w), .. }) if w == operator)
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let sorts = self.sort.len();
+        let mut items = vec![self.and()?];
+        while self.peek_operator("OR") {
+            self.pos += 1;
+            items.push(self.and()?);
+        }
+        // A sort clause would be an always-true OR branch
+        if items.len() > 1 && self.sort.len() > sorts {
+            return Err(QueryError::new(self.sort_positions[sorts], SORT_NOT_TOP_LEVEL));
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Query::Or(items) })
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut items = vec![self.unary()?];
+        loop {
+            if self.peek_operator("AND") {
+                self.pos += 1;
+            } else if self.peek().is_none()
+                || self.peek_operator("OR")
+                || matches!(self.peek(), Some(Token { kind: TokenKind::Close, .. }))
+            {
+                break;
+            }
+            items.push(self.unary()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Query::And(items) })
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        if self.peek_operator("NOT") {
+            self.pos += 1;
+            return self.negate(|p| p.unary());
+        }
+        self.primary()
+    }
+
+    fn negate(&mut self, inner: impl FnOnce(&mut Self) -> Result<Query, QueryError>) -> Result<Query, QueryError> {
+        self.negated += 1;
+        let query = inner(self);
+        self.negated -= 1;
+        Ok(Query::Not(Box::new(query?)))
+    }
+
+    fn primary(&mut self) -> Result<Query, QueryError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(QueryError::new(self.end, "expected a term"));
+        };
+        self.pos += 1;
+
+        match token.kind {
+            TokenKind::Open => {
+                if matches!(self.peek(), Some(Token { kind: TokenKind::Close, .. })) {
+                    return Err(QueryError::new(token.position, "empty group"));
+                }
+                self.groups += 1;
+                let query = self.or();
+                self.groups -= 1;
+                let query = query?;
+                match self.peek() {
+                    Some(Token { kind: TokenKind::Close, .. }) => {
+                        self.pos += 1;
+                        Ok(query)
+                    }
+                    _ => Err(QueryError::new(token.position, "unclosed '('")),
+                }
+            }
+            TokenKind::Close => Err(QueryError::new(token.position, "unexpected ')'")),
+            TokenKind::Phrase(text) => Ok(text_query(&text)),
+            TokenKind::Word(word) if ["AND", "OR", "NOT"].contains(&word.as_str()) => {
+                Err(QueryError::new(token.position, format!("expected a term before {}", word)))
+            }
+            TokenKind::Word(word) => match word.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => {
+                    self.negate(|p| p.word(rest, token.position + 1))
+                }
+                _ => self.word(&word, token.position),
+            },
+        }
+    }
+
+    fn word(&mut self, word: &str, position: usize) -> Result<Query, QueryError> {
+        if let Some((name, value)) = word.split_once(':') {
+            if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic() || c == '_') {
+                return self.field(name, unquote(value), position, position + name.chars().count() + 1);
+            }
+        }
+        if word.starts_with('/') {
+            if let Some((pointer, value)) = word.split_once('=') {
+                return Ok(Query::Pointer {
+                    pointer: pointer.to_string(),
+                    value: pointer_value(value),
+                });
+            }
+        }
+        for (prefix, field) in [("created", TimeField::Created), ("updated", TimeField::Updated)] {
+            if let Some(rest) = word.strip_prefix(prefix) {
+                if rest.starts_with(['<', '>', '=']) {
+                    return time_filter(field, rest, position + prefix.len());
+                }
+            }
+        }
+        Ok(text_query(word))
+    }
+
+    fn field(&mut self, name: &str, value: &str, position: usize, value_position: usize) -> Result<Query, QueryError> {
+        let needs_value = |value: &str| {
+            if value.is_empty() {
+                Err(QueryError::new(value_position, format!("missing value for {}:", name)))
+            } else {
+                Ok(())
+            }
+        };
+
+        match name {
+            "ns" => {
+                needs_value(value)?;
+                Ok(Query::Namespace(value.trim_end_matches('/').to_string()))
+            }
+            "tag" => {
+                needs_value(value)?;
+                Ok(Query::Tag(value.to_string()))
+            }
+            "entity" => {
+                needs_value(value)?;
+                Ok(Query::Entity(value.to_string()))
+            }
+            "project" => {
+                needs_value(value)?;
+                Ok(Query::Or(vec![
+                    Query::Namespace(value.trim_end_matches('/').to_string()),
+                    Query::Pointer {
+                        pointer: "/project".to_string(),
+                        value: Value::String(value.to_string()),
+                    },
+                ]))
+            }
+            "tier" => match value {
+                "working" => Ok(Query::Tier(Tier::Working)),
+                "long_term" => Ok(Query::Tier(Tier::LongTerm)),
+                _ => Err(QueryError::new(value_position, "tier must be 'working' or 'long_term'")),
+            },
+            "created" => time_filter(TimeField::Created, &format!("={}", value), value_position - 1),
+            "updated" => time_filter(TimeField::Updated, &format!("={}", value), value_position - 1),
+            "sort" => {
+                if self.negated > 0 {
+                    return Err(QueryError::new(position, "sort cannot be negated"));
+                }
+                if self.groups > 0 {
+                    return Err(QueryError::new(position, SORT_NOT_TOP_LEVEL));
+                }
+                let (descending, field) = match value.strip_prefix('-') {
+                    Some(field) => (true, field),
+                    None => (false, value),
+                };
+                let field = match field {
+                    "score" => SortField::Score,
+                    "key" => SortField::Key,
+                    "created" => SortField::Created,
+                    "updated" => SortField::Updated,
+                    _ => {
+                        return Err(QueryError::new(
+                            value_position,
+                            "sort field must be score, key, created or updated",
+                        ))
+                    }
+                };
+                self.sort.push(Sort { field, descending });
+                self.sort_positions.push(position);
+                Ok(Query::All)
+            }
+            _ => Err(QueryError::new(
+                position,
+                format!("unknown field '{}'; expected ns, tag, entity, project, tier, created, updated or sort", name),
+            )),
+        }
+    }
+}
+
+/// Free text: one token is a term, several must appear in sequence
+fn text_query(text: &str) -> Query {
+    let mut tokens = tokenize(text);
+    match tokens.len() {
+        0 => Query::All,
+        1 => Query::Term(tokens.remove(0)),
+        _ => Query::Phrase(tokens),
+    }
+}
+
+/// JSON literal if it parses as one, otherwise a string
+fn pointer_value(text: &str) -> Value {
+    if text.starts_with('"') {
+        return Value::String(unquote(text).to_string());
+    }
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// `rest` is the operator followed by a date or RFC 3339 timestamp
+fn time_filter(field: TimeField, rest: &str, position: usize) -> Result<Query, QueryError> {
+    let (op, value) = if let Some(v) = rest.strip_prefix(">=") {
+        (Comparison::Ge, v)
+    } else if let Some(v) = rest.strip_prefix("<=") {
+        (Comparison::Le, v)
+    } else if let Some(v) = rest.strip_prefix('>') {
+        (Comparison::Gt, v)
+    } else if let Some(v) = rest.strip_prefix('<') {
+        (Comparison::Lt, v)
+    } else if let Some(v) = rest.strip_prefix('=') {
+        (Comparison::Eq, v)
+    } else {
+        return Err(QueryError::new(position, "expected <, <=, =, >= or >"));
+    };
+
+    let value_position = position + (rest.len() - value.len());
+    let value = unquote(value);
+    // A date covers the whole day, a timestamp a single instant
+    let (from, to) = if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let from = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        (from, from + Duration::days(1))
+    } else if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        let at = at.with_timezone(&Utc);
+        (at, at + Duration::nanoseconds(1))
+    } else {
+        return Err(QueryError::new(
+            value_position,
+            format!("invalid date '{}', expected YYYY-MM-DD or RFC 3339", value),
+        ));
+    };
+    Ok(Query::Time { field, op, from, to })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Doc {
+        key: &'static str,
+        value: Value,
+        tags: Vec<String>,
+        created: DateTime<Utc>,
+    }
+
+    impl Document for Doc {
+        fn key(&self) -> &str {
+            self.key
+        }
+        fn value(&self) -> &Value {
+            &self.value
+        }
+        fn tags(&self) -> &[String] {
+            &self.tags
+        }
+        fn tier(&self) -> Tier {
+            Tier::Working
+        }
+        fn created(&self) -> DateTime<Utc> {
+            self.created
+        }
+        fn updated(&self) -> DateTime<Utc> {
+            self.created
+        }
+        fn has_term(&self, term: &str) -> bool {
+            tokenize(&crate::text::document_text(self.key, &self.value)).iter().any(|t| t == term)
+        }
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let doc = Doc {
+            key: "tasks/parser",
+            value: serde_json::json!({"title": "Fix the query parser", "status": "open", "prio": 2}),
+            tags: vec!["Bug".to_string()],
+            created: "2025-07-02T10:00:00Z".parse().unwrap(),
+        };
+        let matches = |q: &str| parse(q).unwrap().query.matches(&doc);
+
+        assert!(matches("parser AND (bug OR tag:bug)"));
+        assert!(matches("\"query parser\" ns:tasks tier:working"));
+        assert!(!matches("\"parser query\""));
+        assert!(matches("/status=open /prio=2 -/status=closed"));
+        assert!(matches("created>2025-07-01 created<=2025-07-02 created:2025-07-02"));
+        assert!(!matches("created>2025-07-02"));
+        assert!(!matches("ns:task"));
+        assert!(matches("NOT tag:feature parser"));
+
+        let parsed = parse("parser sort:-created sort:key").unwrap();
+        assert_eq!(parsed.sort[0], Sort { field: SortField::Created, descending: true });
+        assert_eq!(parsed.query.positive_terms(), vec!["parser"]);
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let error = |q: &str| parse(q).unwrap_err();
+        assert_eq!(error("parser AND").position, 10);
+        assert_eq!(error("(a OR b").position, 0);
+        assert_eq!(error("a b)").position, 3);
+        assert_eq!(
+            error("foo:bar").message,
+            "unknown field 'foo'; expected ns, tag, entity, project, tier, created, updated or sort"
+        );
+        assert_eq!(error("tier:hot").position, 5);
+        assert_eq!(error("x created>2025-13-01").position, 10);
+        assert_eq!(error("tag:\"open").position, 4);
+        assert_eq!(error("NOT sort:key").message, "sort cannot be negated");
+        assert_eq!(error("rust OR sort:key").position, 8);
+        assert_eq!(error("sort:key OR rust").message, SORT_NOT_TOP_LEVEL);
+        assert_eq!(error("rust (sort:key)").position, 6);
+        assert!(parse("rust sort:key").is_ok());
+    }
+}
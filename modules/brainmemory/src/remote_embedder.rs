@@ -0,0 +1,502 @@
+// SimplyKI BrainMemory - Remote Embedding Provider
+// Erstellt: 2026-10-19 16:30:00 CEST
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::embedding::{normalize, EmbedError, Embedder};
+use crate::text::fnv1a;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteEmbedderConfig {
+    /// Plain `http://host[:port]/path` endpoint
+    pub url: String,
+    /// Sent as `model` in the request body when set
+    pub model: Option<String>,
+    pub dimensions: usize,
+    pub batch_size: usize,
+    /// Per attempt: bounds connecting and, once connected, sending the
+    /// request and reading the whole response
+    pub timeout_ms: u64,
+    /// Longer responses are rejected without retry
+    pub max_response_bytes: usize,
+    /// Attempts after the first on I/O errors, 429 and 5xx
+    pub retries: u32,
+    /// Doubled after every failed attempt
+    pub retry_backoff_ms: u64,
+    /// Embeddings kept in memory; 0 disables the cache
+    pub cache_capacity: usize,
+}
+
+impl Default for RemoteEmbedderConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://127.0.0.1:8080/embed".to_string(),
+            model: None,
+            dimensions: 384,
+            batch_size: 32,
+            timeout_ms: 5_000,
+            max_response_bytes: 16 * 1024 * 1024,
+            retries: 2,
+            retry_backoff_ms: 100,
+            cache_capacity: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    fn parse(url: &str) -> Result<Self, EmbedError> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| EmbedError::new(format!("unsupported url '{}', expected http://", url)))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse().map_err(|_| EmbedError::new(format!("invalid port in '{}'", url)))?;
+                (host, port)
+            }
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(EmbedError::new(format!("missing host in '{}'", url)));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Embeddings by content hash, oldest evicted first
+#[derive(Debug, Default)]
+struct EmbeddingCache {
+    vectors: HashMap<u64, Vec<f32>>,
+    order: VecDeque<u64>,
+}
+
+impl EmbeddingCache {
+    fn get(&self, hash: u64) -> Option<Vec<f32>> {
+        self.vectors.get(&hash).cloned()
+    }
+
+    fn insert(&mut self, hash: u64, vector: Vec<f32>, capacity: usize) {
+        if capacity == 0 || self.vectors.insert(hash, vector).is_some() {
+            return;
+        }
+        self.order.push_back(hash);
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.vectors.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Calls an HTTP embedding server. The request body is
+/// `{"input": [..texts], "model": ..}`; the response may be OpenAI style
+/// (`{"data": [{"embedding": [..]}]}`), `{"embeddings": [[..]]}` or a bare
+/// array of vectors.
+///
+/// Requests block the calling thread, retries included. Callers holding a
+/// lock should embed ahead with `Precomputed`.
+///
+/// [`Precomputed`]: crate::embedding::Precomputed
+#[derive(Debug)]
+pub struct RemoteEmbedder {
+    config: RemoteEmbedderConfig,
+    endpoint: Endpoint,
+    cache: Mutex<EmbeddingCache>,
+}
+
+impl RemoteEmbedder {
+    pub fn new(config: RemoteEmbedderConfig) -> Result<Self, EmbedError> {
+        let endpoint = Endpoint::parse(&config.url)?;
+        if config.dimensions == 0 || config.batch_size == 0 {
+            return Err(EmbedError::new("dimensions and batch_size must be positive"));
+        }
+        Ok(Self {
+            config,
+            endpoint,
+            cache: Mutex::new(EmbeddingCache::default()),
+        })
+    }
+
+    pub fn config(&self) -> &RemoteEmbedderConfig {
+        &self.config
+    }
+
+    fn request_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        let mut body = serde_json::json!({ "input": texts });
+        if let Some(model) = &self.config.model {
+            body["model"] = Value::String(model.clone());
+        }
+        let body = body.to_string();
+
+        let mut attempt = 0;
+        loop {
+            match self.post(&body) {
+                Ok(response) => return self.parse_vectors(&response, texts.len()),
+                Err(e) if e.retryable && attempt < self.config.retries => {
+                    let backoff = self.config.retry_backoff_ms.saturating_mul(1 << attempt.min(16));
+                    tracing::debug!("embedding request failed, retrying: {}", e.error);
+                    thread::sleep(Duration::from_millis(backoff));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.error),
+            }
+        }
+    }
+
+    fn post(&self, body: &str) -> Result<Value, Attempt> {
+        let timeout = Duration::from_millis(self.config.timeout_ms.max(1));
+        let addr = (self.endpoint.host.as_str(), self.endpoint.port)
+            .to_socket_addrs()
+            .map_err(Attempt::io)?
+            .next()
+            .ok_or_else(|| Attempt::fatal(format!("cannot resolve {}", self.endpoint.host)))?;
+
+        let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(Attempt::io)?;
+        // Socket timeouts apply per call, the deadline to the whole exchange
+        let deadline = Instant::now() + timeout;
+        stream.set_write_timeout(Some(timeout)).map_err(Attempt::io)?;
+
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nAccept: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.endpoint.path,
+            self.endpoint.host,
+            self.endpoint.port,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).map_err(Attempt::io)?;
+
+        let raw = read_response(&mut stream, deadline, self.config.max_response_bytes)?;
+        let (status, body) = parse_response(&raw).map_err(Attempt::fatal)?;
+
+        if status == 429 || status >= 500 {
+            return Err(Attempt::retry(format!("server returned {}", status)));
+        }
+        if !(200..300).contains(&status) {
+            let text = String::from_utf8_lossy(&body);
+            return Err(Attempt::fatal(format!("server returned {}: {}", status, text.trim())));
+        }
+        serde_json::from_slice(&body).map_err(|e| Attempt::fatal(format!("invalid response body: {}", e)))
+    }
+
+    fn parse_vectors(&self, response: &Value, expected: usize) -> Result<Vec<Vec<f32>>, EmbedError> {
+        let items = match response {
+            Value::Array(items) => items,
+            Value::Object(map) => match (map.get("data"), map.get("embeddings")) {
+                (Some(Value::Array(items)), _) | (_, Some(Value::Array(items))) => items,
+                _ => return Err(EmbedError::new("response has neither 'data' nor 'embeddings'")),
+            },
+            _ => return Err(EmbedError::new("response is not a JSON object or array")),
+        };
+        if items.len() != expected {
+            return Err(EmbedError::new(format!("expected {} embeddings, got {}", expected, items.len())));
+        }
+
+        items
+            .iter()
+            .map(|item| {
+                let numbers = item.get("embedding").unwrap_or(item);
+                let mut vector: Vec<f32> = numbers
+                    .as_array()
+                    .ok_or_else(|| EmbedError::new("embedding is not an array"))?
+                    .iter()
+                    .map(|x| x.as_f64().map(|x| x as f32))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| EmbedError::new("embedding contains a non-number"))?;
+                if vector.len() != self.config.dimensions {
+                    return Err(EmbedError::new(format!(
+                        "expected {} dimensions, got {}",
+                        self.config.dimensions,
+                        vector.len()
+                    )));
+                }
+                normalize(&mut vector);
+                Ok(vector)
+            })
+            .collect()
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    fn dimensions(&self) -> usize {
+        self.config.dimensions
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, EmbedError> {
+        self.embed_batch(&[text]).map(|mut vectors| vectors.remove(0))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        let hashes: Vec<u64> = texts.iter().map(|t| fnv1a(t.as_bytes())).collect();
+        let mut vectors: Vec<Option<Vec<f32>>> = {
+            let cache = self.cache.lock().unwrap();
+            hashes.iter().map(|h| cache.get(*h)).collect()
+        };
+
+        // Each distinct text that is not cached is requested once
+        let mut missing: Vec<(u64, &str)> = Vec::new();
+        for (i, vector) in vectors.iter().enumerate() {
+            if vector.is_none() && !missing.iter().any(|(h, _)| *h == hashes[i]) {
+                missing.push((hashes[i], texts[i]));
+            }
+        }
+
+        for chunk in missing.chunks(self.config.batch_size) {
+            let batch: Vec<&str> = chunk.iter().map(|(_, t)| *t).collect();
+            let fetched = self.request_batch(&batch)?;
+
+            let mut cache = self.cache.lock().unwrap();
+            for ((hash, _), vector) in chunk.iter().zip(fetched) {
+                for (i, slot) in vectors.iter_mut().enumerate() {
+                    if hashes[i] == *hash {
+                        *slot = Some(vector.clone());
+                    }
+                }
+                cache.insert(*hash, vector, self.config.cache_capacity);
+            }
+        }
+
+        Ok(vectors.into_iter().map(|v| v.unwrap_or_default()).collect())
+    }
+}
+
+/// Outcome of a failed request attempt
+struct Attempt {
+    error: EmbedError,
+    retryable: bool,
+}
+
+impl Attempt {
+    fn io(e: std::io::Error) -> Self {
+        Self::retry(e.to_string())
+    }
+
+    fn retry(message: String) -> Self {
+        Self { error: EmbedError::new(message), retryable: true }
+    }
+
+    fn fatal(message: String) -> Self {
+        Self { error: EmbedError::new(message), retryable: false }
+    }
+}
+
+/// Read until the server closes the connection, the deadline passes or
+/// the response outgrows `limit`
+fn read_response(stream: &mut TcpStream, deadline: Instant, limit: usize) -> Result<Vec<u8>, Attempt> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Attempt::retry("response timed out".to_string()));
+        }
+        stream.set_read_timeout(Some(remaining)).map_err(Attempt::io)?;
+        let n = stream.read(&mut buf).map_err(|e| match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => Attempt::retry("response timed out".to_string()),
+            _ => Attempt::io(e),
+        })?;
+        if n == 0 {
+            return Ok(raw);
+        }
+        raw.extend_from_slice(&buf[..n]);
+        if raw.len() > limit {
+            return Err(Attempt::fatal(format!("response larger than {} bytes", limit)));
+        }
+    }
+}
+
+/// Status code and body of a raw HTTP/1.1 response
+fn parse_response(raw: &[u8]) -> Result<(u16, Vec<u8>), String> {
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("truncated response headers")?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let body = &raw[split + 4..];
+
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or("malformed status line")?;
+    let chunked = lines.any(|line| {
+        let lower = line.to_ascii_lowercase();
+        lower.starts_with("transfer-encoding:") && lower.contains("chunked")
+    });
+
+    if chunked {
+        Ok((status, decode_chunked(body)?))
+    } else {
+        Ok((status, body.to_vec()))
+    }
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n").ok_or("truncated chunk")?;
+        let size_text = String::from_utf8_lossy(&body[..line_end]);
+        let size_text = size_text.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_text, 16).map_err(|_| "invalid chunk size")?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(out);
+        }
+        if body.len() < size + 2 {
+            return Err("truncated chunk".to_string());
+        }
+        out.extend_from_slice(&body[..size]);
+        body = &body[size + 2..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Answers each connection with the next status; 200 responses embed
+    /// every input as `[len, 1, 0]`
+    fn mock_server(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v1/embeddings", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+
+        thread::spawn(move || {
+            for (stream, status) in listener.incoming().zip(statuses) {
+                let mut stream = stream.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                let body = loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let Some(split) = raw.windows(4).position(|w| w == b"\r\n\r\n") else { continue };
+                    let head = String::from_utf8_lossy(&raw[..split]).to_lowercase();
+                    let len: usize = head
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length:"))
+                        .map(|v| v.trim().parse().unwrap())
+                        .unwrap();
+                    if raw.len() >= split + 4 + len {
+                        break raw[split + 4..split + 4 + len].to_vec();
+                    }
+                };
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let response = if status == 200 {
+                    let data: Vec<Value> = request["input"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|t| serde_json::json!({"embedding": [t.as_str().unwrap().len(), 1, 0]}))
+                        .collect();
+                    serde_json::json!({ "data": data }).to_string()
+                } else {
+                    "{}".to_string()
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                );
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn config(url: String) -> RemoteEmbedderConfig {
+        RemoteEmbedderConfig {
+            url,
+            dimensions: 3,
+            batch_size: 2,
+            retry_backoff_ms: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_batching_and_cache() {
+        let (url, requests) = mock_server(vec![200; 4]);
+        let embedder = RemoteEmbedder::new(config(url)).unwrap();
+
+        let vectors = embedder.embed_batch(&["a", "bbb", "a", "cc"]).unwrap();
+        assert_eq!(vectors.len(), 4);
+        assert_eq!(vectors[0], vectors[2]);
+        assert!(vectors[1][0] > vectors[0][0]);
+        // Three distinct texts in batches of two
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        embedder.embed("bbb").unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_retries_and_errors() {
+        let (url, requests) = mock_server(vec![503, 200, 400]);
+        let embedder = RemoteEmbedder::new(config(url)).unwrap();
+
+        assert!(embedder.embed("x").is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        let error = embedder.embed("y").unwrap_err();
+        assert!(error.message.contains("400"), "{}", error);
+
+        assert!(RemoteEmbedder::new(RemoteEmbedderConfig { url: "https://x".into(), ..Default::default() }).is_err());
+        assert_eq!(decode_chunked(b"4\r\nWiki\r\n3;x=1\r\npia\r\n0\r\n\r\n").unwrap(), b"Wikipia");
+    }
+
+    #[test]
+    fn test_deadline_and_size_limit() {
+        // Trickles a byte at a time, so no single read ever times out
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let _ = stream.read(&mut [0u8; 4096]);
+                    while stream.write_all(b"x").is_ok() {
+                        thread::sleep(Duration::from_millis(20));
+                    }
+                });
+            }
+        });
+
+        let slow = RemoteEmbedder::new(RemoteEmbedderConfig { timeout_ms: 200, retries: 0, ..config(url.clone()) }).unwrap();
+        let started = Instant::now();
+        assert!(slow.embed("x").unwrap_err().message.contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let capped = RemoteEmbedder::new(RemoteEmbedderConfig { max_response_bytes: 4, retries: 0, ..config(url) }).unwrap();
+        assert!(capped.embed("x").unwrap_err().message.contains("larger than 4 bytes"));
+    }
+}
@@ -0,0 +1,377 @@
+// SimplyKI BrainMemory - JSON Schema Validation per Key Prefix
+// Erstellt: 2026-10-19 11:40:00 CEST
+
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// One failed constraint, located by JSON pointer into the stored value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() { "/" } else { &self.pointer };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+/// Schemas keyed by key prefix (e.g. `session/`); the longest matching
+/// prefix wins. Supports the commonly used draft-07 keywords: `type`,
+/// `enum`, `const`, `properties`, `required`, `additionalProperties`,
+/// `items`, `minItems`/`maxItems`, `minLength`/`maxLength` and the numeric
+/// bounds, plus annotations like `title`. Schemas using other keywords are
+/// refused when registered rather than half enforced.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    schemas: BTreeMap<String, Value>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, prefix: &str, schema: Value) -> Result<(), String> {
+        check_schema(&schema, &mut String::new())?;
+        self.schemas.insert(prefix.to_string(), schema);
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, prefix: &str) -> Option<Value> {
+        self.schemas.remove(prefix)
+    }
+
+    pub fn schemas(&self) -> &BTreeMap<String, Value> {
+        &self.schemas
+    }
+
+    /// Schema responsible for `key`, if any
+    pub fn schema_for(&self, key: &str) -> Option<(&str, &Value)> {
+        self.schemas
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, schema)| (prefix.as_str(), schema))
+    }
+
+    pub fn validate(&self, key: &str, value: &Value) -> Result<(), Vec<SchemaViolation>> {
+        let Some((_, schema)) = self.schema_for(key) else {
+            return Ok(());
+        };
+        let mut errors = Vec::new();
+        validate(schema, value, &mut String::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "type", "enum", "const", "properties", "required", "additionalProperties", "items",
+    "minItems", "maxItems", "minLength", "maxLength",
+    "minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum",
+];
+
+const TYPES: &[&str] = &["null", "boolean", "integer", "number", "string", "array", "object"];
+
+/// Keywords without effect on validation
+const ANNOTATIONS: &[&str] = &[
+    "$schema", "$id", "$comment", "title", "description", "default", "examples",
+];
+
+/// Refuse schemas that are not objects or booleans or that use keywords
+/// `validate` does not enforce; `pointer` locates the subschema
+fn check_schema(schema: &Value, pointer: &mut String) -> Result<(), String> {
+    let location = |pointer: &str| if pointer.is_empty() { "/".to_string() } else { pointer.to_string() };
+    let schema = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return Err(format!("{}: schema must be a JSON object or boolean", location(pointer))),
+    };
+
+    for (keyword, value) in schema {
+        if ANNOTATIONS.contains(&keyword.as_str()) {
+            continue;
+        }
+        if !KEYWORDS.contains(&keyword.as_str()) {
+            return Err(format!("{}: unsupported keyword \"{}\"", location(pointer), keyword));
+        }
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&escape_pointer(keyword));
+        // Values of the wrong shape would be skipped by `validate`
+        let shape = match keyword.as_str() {
+            "type" => match value {
+                Value::String(t) => TYPES.contains(&t.as_str()),
+                Value::Array(ts) => ts.iter().all(|t| t.as_str().is_some_and(|t| TYPES.contains(&t))),
+                _ => false,
+            }
+            .then_some(())
+            .ok_or("type must be a type name or an array of them"),
+            "enum" => value.is_array().then_some(()).ok_or("enum must be an array"),
+            "required" => value
+                .as_array()
+                .is_some_and(|names| names.iter().all(Value::is_string))
+                .then_some(())
+                .ok_or("required must be an array of strings"),
+            "minItems" | "maxItems" | "minLength" | "maxLength" => {
+                value.is_u64().then_some(()).ok_or("must be a non-negative integer")
+            }
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => {
+                value.is_number().then_some(()).ok_or("must be a number")
+            }
+            _ => Ok(()),
+        };
+        if let Err(message) = shape {
+            return Err(format!("{}: {}", pointer, message));
+        }
+        match keyword.as_str() {
+            "items" | "additionalProperties" => check_schema(value, pointer)?,
+            "properties" => {
+                let Some(properties) = value.as_object() else {
+                    return Err(format!("{}: properties must be an object", pointer));
+                };
+                for (name, property) in properties {
+                    let len = pointer.len();
+                    pointer.push('/');
+                    pointer.push_str(&escape_pointer(name));
+                    check_schema(property, pointer)?;
+                    pointer.truncate(len);
+                }
+            }
+            _ => {}
+        }
+        pointer.truncate(len);
+    }
+    Ok(())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    let actual = type_name(value);
+    actual == expected
+        || (expected == "number" && actual == "integer")
+        || (expected == "integer" && value.as_f64().is_some_and(|f| f.fract() == 0.0))
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn validate(schema: &Value, value: &Value, pointer: &mut String, errors: &mut Vec<SchemaViolation>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            push(errors, pointer, "no value is allowed here".to_string());
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| type_matches(t, value)) {
+            push(errors, pointer, format!("expected {}, found {}", allowed.join(" or "), type_name(value)));
+            // Further keywords would only repeat the type mismatch
+            return;
+        }
+    }
+
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            push(errors, pointer, format!("value must be one of {}", Value::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            push(errors, pointer, format!("value must equal {}", expected));
+        }
+    }
+
+    match value {
+        Value::Number(n) => validate_number(schema, n.as_f64().unwrap_or(0.0), pointer, errors),
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    push(errors, pointer, format!("string shorter than {} characters", min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if len > max {
+                    push(errors, pointer, format!("string longer than {} characters", max));
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if len < min {
+                    push(errors, pointer, format!("array has fewer than {} items", min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if len > max {
+                    push(errors, pointer, format!("array has more than {} items", max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    let len = pointer.len();
+                    pointer.push_str(&format!("/{}", i));
+                    validate(item_schema, item, pointer, errors);
+                    pointer.truncate(len);
+                }
+            }
+        }
+        Value::Object(map) => validate_object(schema, map, pointer, errors),
+        _ => {}
+    }
+}
+
+fn validate_number(schema: &Map<String, Value>, n: f64, pointer: &str, errors: &mut Vec<SchemaViolation>) {
+    let bound = |name: &str| schema.get(name).and_then(Value::as_f64);
+    if let Some(min) = bound("minimum") {
+        if n < min {
+            push(errors, pointer, format!("value is less than minimum {}", min));
+        }
+    }
+    if let Some(max) = bound("maximum") {
+        if n > max {
+            push(errors, pointer, format!("value is greater than maximum {}", max));
+        }
+    }
+    if let Some(min) = bound("exclusiveMinimum") {
+        if n <= min {
+            push(errors, pointer, format!("value must be greater than {}", min));
+        }
+    }
+    if let Some(max) = bound("exclusiveMaximum") {
+        if n >= max {
+            push(errors, pointer, format!("value must be less than {}", max));
+        }
+    }
+}
+
+fn validate_object(
+    schema: &Map<String, Value>,
+    map: &Map<String, Value>,
+    pointer: &mut String,
+    errors: &mut Vec<SchemaViolation>,
+) {
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !map.contains_key(name) {
+                push(errors, pointer, format!("missing required property \"{}\"", name));
+            }
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let additional = schema.get("additionalProperties");
+    for (name, item) in map {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&escape_pointer(name));
+        match (properties.and_then(|p| p.get(name)), additional) {
+            (Some(property_schema), _) => validate(property_schema, item, pointer, errors),
+            (None, Some(Value::Bool(false))) => {
+                push(errors, pointer, format!("unexpected property \"{}\"", name));
+            }
+            (None, Some(additional_schema)) => validate(additional_schema, item, pointer, errors),
+            (None, None) => {}
+        }
+        pointer.truncate(len);
+    }
+}
+
+fn push(errors: &mut Vec<SchemaViolation>, pointer: &str, message: String) {
+    errors.push(SchemaViolation {
+        pointer: pointer.to_string(),
+        message,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn task_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["title", "priority"],
+            "additionalProperties": false,
+            "properties": {
+                "title": {"type": "string", "minLength": 1},
+                "priority": {"type": "integer", "minimum": 1, "maximum": 5},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        })
+    }
+
+    #[test]
+    fn test_violations_carry_pointers() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("task/", task_schema()).unwrap();
+
+        let errors = registry
+            .validate("task/1", &json!({"title": "", "priority": 9, "tags": ["ok", 3], "a/b": 1}))
+            .unwrap_err();
+        let pointers: Vec<&str> = errors.iter().map(|e| e.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/a~1b", "/priority", "/tags/1", "/title"]);
+
+        let errors = registry.validate("task/2", &json!({"title": "x"})).unwrap_err();
+        assert_eq!(errors[0].pointer, "");
+        assert!(errors[0].message.contains("priority"));
+
+        assert!(registry.validate("task/3", &json!({"title": "x", "priority": 2})).is_ok());
+        assert!(registry.validate("other", &json!(42)).is_ok());
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("task/", task_schema()).unwrap();
+        registry.register("task/draft/", json!(true)).unwrap();
+        assert!(registry.register("bad/", json!(3)).is_err());
+        assert_eq!(
+            registry.register("bad/", json!({"title": "x", "properties": {"id": {"pattern": "^[0-9]+$"}}})),
+            Err("/properties/id: unsupported keyword \"pattern\"".to_string())
+        );
+        assert!(registry.register("bad/", json!({"oneOf": [true]})).is_err());
+        assert_eq!(
+            registry.register("bad/", json!({"required": "title"})),
+            Err("/required: required must be an array of strings".to_string())
+        );
+        assert!(registry.register("bad/", json!({"properties": {"a": {"enum": "x"}}})).is_err());
+        assert!(registry.register("bad/", json!({"type": "text"})).is_err());
+        assert!(registry.schemas().get("bad/").is_none());
+
+        assert!(registry.validate("task/draft/1", &json!("anything")).is_ok());
+        assert!(registry.validate("task/1", &json!("anything")).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+// SimplyKI BrainMemory - Search Types
+// Erstellt: 2026-10-19 13:50:00 CEST
+
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::embedding::Precomputed;
+use crate::facets::{FacetOptions, Facets};
+use crate::fuzzy::FuzzyOptions;
+use crate::pattern::KeyMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    Working,
+    LongTerm,
+}
+
+/// Okapi BM25 parameters: `k1` controls term frequency saturation,
+/// `b` how strongly long documents are penalized
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bm25Params {
+    pub k1: f64,
+    pub b: f64,
+}
+
+impl Default for Bm25Params {
+    fn default() -> Self {
+        Self { k1: 1.2, b: 0.75 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// BM25 over keys and values
+    #[default]
+    Keyword,
+    /// Cosine similarity of embeddings
+    Semantic,
+    /// Both, combined with `SearchOptions::fusion`
+    Hybrid,
+}
+
+/// How hybrid search merges the keyword and semantic rankings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Fusion {
+    /// Reciprocal rank fusion, `1 / (k + rank)` summed over both lists;
+    /// ignores the raw scores, which are not comparable
+    Rrf {
+        #[serde(default = "default_rrf_k")]
+        k: f64,
+    },
+    /// Scores min-max normalized per list, then mixed linearly
+    Weighted {
+        #[serde(default = "default_semantic_weight")]
+        semantic_weight: f64,
+    },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::Rrf { k: default_rrf_k() }
+    }
+}
+
+fn default_rrf_k() -> f64 {
+    60.0
+}
+
+fn default_semantic_weight() -> f64 {
+    0.5
+}
+
+/// Merge a keyword and a semantic result list into one ranking
+pub fn fuse(keyword: Vec<(String, f64)>, semantic: Vec<(String, f64)>, fusion: &Fusion) -> Vec<(String, f64)> {
+    let (keyword_weight, semantic_weight) = match *fusion {
+        Fusion::Rrf { .. } => (1.0, 1.0),
+        Fusion::Weighted { semantic_weight } => (1.0 - semantic_weight, semantic_weight),
+    };
+
+    let mut fused: HashMap<String, f64> = HashMap::new();
+    for (mut list, weight) in [(keyword, keyword_weight), (semantic, semantic_weight)] {
+        list.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        match *fusion {
+            Fusion::Rrf { k } => {
+                for (rank, (key, _)) in list.into_iter().enumerate() {
+                    *fused.entry(key).or_insert(0.0) += weight / (k + rank as f64 + 1.0);
+                }
+            }
+            Fusion::Weighted { .. } => {
+                let max = list.first().map_or(0.0, |(_, s)| *s);
+                let min = list.last().map_or(0.0, |(_, s)| *s);
+                for (key, score) in list {
+                    let normalized = if max > min { (score - min) / (max - min) } else { 1.0 };
+                    *fused.entry(key).or_insert(0.0) += weight * normalized;
+                }
+            }
+        }
+    }
+    fused.into_iter().collect()
+}
+
+/// Candidate set of semantic and hybrid search
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SemanticOptions {
+    /// Nearest neighbours fetched from the vector index; paginated
+    /// searches count and page through at most this many semantic matches
+    pub k: usize,
+    /// Cosine similarity a neighbour needs to count as a match
+    pub min_similarity: f64,
+}
+
+impl Default for SemanticOptions {
+    fn default() -> Self {
+        Self {
+            k: 200,
+            min_similarity: 0.2,
+        }
+    }
+}
+
+/// Blend of relevance with usage signals. Each signal lies in `0..=1` and
+/// scales the score by `1 + weight * signal`; all weights default to 0.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ranking {
+    /// Halves for every `recency_half_life_secs` since the last access
+    pub recency: f64,
+    pub recency_half_life_secs: f64,
+    /// `count / (count + 10)` of the entry's access count
+    pub frequency: f64,
+    /// Share of the recently stored keys associated with the entry
+    pub association: f64,
+}
+
+impl Default for Ranking {
+    fn default() -> Self {
+        Self {
+            recency: 0.0,
+            recency_half_life_secs: 3600.0,
+            frequency: 0.0,
+            association: 0.0,
+        }
+    }
+}
+
+impl Ranking {
+    pub fn is_active(&self) -> bool {
+        self.recency != 0.0 || self.frequency != 0.0 || self.association != 0.0
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchOptions {
+    pub limit: usize,
+    pub mode: SearchMode,
+    pub fusion: Fusion,
+    /// Restrict results to one tier; both tiers are searched by default
+    pub tier: Option<Tier>,
+    pub bm25: Bm25Params,
+    /// Typo-tolerant matching; exact terms only when `None`
+    pub fuzzy: Option<FuzzyOptions>,
+    pub semantic: SemanticOptions,
+    pub ranking: Ranking,
+    /// Count matches per facet; only paginated searches report facets
+    pub facets: Option<FacetOptions>,
+    /// Only keys matching the pattern; with an empty query, every such key
+    #[serde(skip)]
+    pub keys: Option<KeyMatcher>,
+    /// Embedding of the query computed ahead, see `Precomputed`
+    #[serde(skip)]
+    pub query_embedding: Option<Precomputed>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            limit: 10,
+            mode: SearchMode::Keyword,
+            fusion: Fusion::default(),
+            tier: None,
+            bm25: Bm25Params::default(),
+            fuzzy: None,
+            semantic: SemanticOptions::default(),
+            ranking: Ranking::default(),
+            facets: None,
+            keys: None,
+            query_embedding: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub key: String,
+    pub score: f64,
+    pub tier: Tier,
+}
+
+/// One page of ranked hits
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchPage {
+    pub hits: Vec<SearchHit>,
+    /// Matches across all pages
+    pub total: usize,
+    /// Continues after the last hit; `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Over all matches, when `SearchOptions::facets` asks for them
+    pub facets: Option<Facets>,
+}
+
+/// Position in the stored results of a paginated search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub result_set: u64,
+    pub offset: usize,
+}
+
+impl Cursor {
+    /// Opaque form handed to clients
+    pub fn encode(&self) -> String {
+        format!("{:x}.{:x}", self.result_set, self.offset)
+    }
+
+    pub fn decode(text: &str) -> Result<Self, CursorError> {
+        let (result_set, offset) = text.split_once('.').ok_or(CursorError::Invalid)?;
+        Ok(Self {
+            result_set: u64::from_str_radix(result_set, 16).map_err(|_| CursorError::Invalid)?,
+            offset: usize::from_str_radix(offset, 16).map_err(|_| CursorError::Invalid)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorError {
+    Invalid,
+    /// The results outlived their time to live or were dropped to keep
+    /// the stored results within their memory budget
+    Expired,
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CursorError::Invalid => write!(f, "invalid cursor"),
+            CursorError::Expired => write!(f, "cursor expired, search again"),
+        }
+    }
+}
+
+impl std::error::Error for CursorError {}
+
+/// Why a hit scored the way it did
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    /// Score the hit was ranked by
+    pub score: f64,
+    /// BM25 over keys and values
+    pub lexical: f64,
+    /// Cosine similarity to the query; `None` without an embedding
+    pub semantic: Option<f64>,
+    pub recency_boost: f64,
+    pub frequency_boost: f64,
+    pub association_boost: f64,
+    pub terms: Vec<TermScore>,
+    /// JSON pointers of matching string fields; `key` if the key matched
+    pub fields: Vec<String>,
+}
+
+/// BM25 contribution of one indexed term
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TermScore {
+    pub term: String,
+    /// Occurrences in the entry
+    pub frequency: u32,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// Completes the last word of the input with an indexed term
+    Term,
+    /// A stored key starting with the input
+    Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+    /// Number of entries containing the term; 1 for keys
+    pub count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[(&str, f64)]) -> Vec<(String, f64)> {
+        items.iter().map(|(k, s)| (k.to_string(), *s)).collect()
+    }
+
+    fn ranked(mut fused: Vec<(String, f64)>) -> Vec<String> {
+        fused.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        fused.into_iter().map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn test_fuse() {
+        let keyword = list(&[("server.rs", 9.0), ("both", 4.0), ("readme", 1.0)]);
+        let semantic = list(&[("paraphrase", 0.9), ("both", 0.8)]);
+
+        let rrf = ranked(fuse(keyword.clone(), semantic.clone(), &Fusion::default()));
+        assert_eq!(rrf[0], "both");
+        assert_eq!(rrf.len(), 4);
+
+        let keyword_only = ranked(fuse(keyword, semantic, &Fusion::Weighted { semantic_weight: 0.0 }));
+        assert_eq!(keyword_only[0], "server.rs");
+
+        let fusion: Fusion = serde_json::from_str(r#"{"method": "weighted"}"#).unwrap();
+        assert_eq!(fusion, Fusion::Weighted { semantic_weight: 0.5 });
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor { result_set: 42, offset: 20 };
+        assert_eq!(Cursor::decode(&cursor.encode()), Ok(cursor));
+        assert_eq!(Cursor::decode("not a cursor"), Err(CursorError::Invalid));
+    }
+}
@@ -1,15 +1,36 @@
 // SimplyKI BrainMemory - Server Mode
 // Erstellt: 2025-07-24 16:40:00 CEST
 
-use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use warp::{Filter, Rejection, Reply};
+use warp::filters::BoxedFilter;
+use warp::http::StatusCode;
+use warp::reply::Response;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::time::interval;
 
-mod memory;
-use memory::{BrainMemory, MemoryStats};
+use crate::analysis::AnalyzerConfig;
+use crate::consolidation::ConsolidatorConfig;
+use crate::context::ContextOptions;
+use crate::embedding::{Embedder, Precomputed};
+use crate::entity::EntityKind;
+use crate::episode::{EpisodeError, EpisodeId, EpisodeQuery};
+use crate::error::StoreError;
+use crate::facets::{FacetOptions, Facets};
+use crate::fuzzy::FuzzyOptions;
+use crate::memory::{AutoTagConfig, BrainMemory, StoreOptions, REEMBED_BATCH};
+use crate::pattern::{KeyMatcher, KeyPattern, PatternError};
+use crate::remote_embedder::{RemoteEmbedder, RemoteEmbedderConfig};
+use crate::snippet::{Snippet, SnippetOptions};
+use crate::text::document_text;
+use crate::watch::WatchId;
+use crate::search::{Bm25Params, CursorError, Explanation, Fusion, Ranking, SearchMode, SearchOptions, SemanticOptions, Tier};
+
+/// Upper bound for `POST /store` bodies, checked before the JSON is parsed;
+/// the per-value limit of `StoreLimits` applies afterwards
+const MAX_STORE_BODY_BYTES: u64 = 4 * 1024 * 1024;
 
 #[derive(Debug, Clone)]
 struct ServerState {
@@ -36,8 +57,12 @@ struct PerformanceInfo {
 struct StoreRequest {
     key: String,
     value: serde_json::Value,
+    /// Accepted for compatibility; new entries always start in working memory
     #[serde(default = "default_memory_type")]
+    #[allow(dead_code)]
     memory_type: String,
+    #[serde(flatten)]
+    options: StoreOptions,
 }
 
 fn default_memory_type() -> String {
@@ -61,9 +86,118 @@ struct RetrieveResponse {
     cache_hit: bool,
 }
 
+#[derive(Deserialize)]
+struct PatchRequest {
+    key: String,
+    patch: serde_json::Value,
+}
+
 #[derive(Deserialize)]
 struct SearchRequest {
+    #[serde(default)]
+    query: String,
+    /// Structured query, see `crate::query`; filters the `query` results
+    q: Option<String>,
+    #[serde(default)]
+    snippet: SnippetOptions,
+    /// Attach a score breakdown to every match
+    #[serde(default)]
+    explain: bool,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    /// `next_cursor` of the previous page; the rest of the request only
+    /// shapes snippets and explanations then
+    cursor: Option<String>,
+    #[serde(default)]
+    mode: SearchMode,
+    fusion: Option<Fusion>,
+    tier: Option<Tier>,
+    bm25: Option<Bm25Params>,
+    fuzzy: Option<FuzzyOptions>,
+    semantic: Option<SemanticOptions>,
+    ranking: Option<Ranking>,
+    facets: Option<FacetOptions>,
+    #[serde(flatten)]
+    keys: PatternParams,
+}
+
+/// `glob` or `regex` over whole keys, see `crate::pattern`
+#[derive(Deserialize, Default)]
+struct PatternParams {
+    glob: Option<String>,
+    regex: Option<String>,
+}
+
+impl PatternParams {
+    fn compile(&self) -> Result<Option<KeyMatcher>, PatternError> {
+        let pattern = match (&self.glob, &self.regex) {
+            (None, None) => return Ok(None),
+            (Some(glob), None) => KeyPattern::Glob(glob.clone()),
+            (None, Some(regex)) => KeyPattern::Regex(regex.clone()),
+            (Some(_), Some(_)) => {
+                return Err(PatternError { message: "give either glob or regex".to_string() });
+            }
+        };
+        pattern.compile().map(Some)
+    }
+}
+
+fn pattern_error(e: PatternError) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+#[derive(Deserialize)]
+struct ContextRequest {
+    task: String,
+    #[serde(flatten)]
+    options: ContextOptions,
+}
+
+#[derive(Deserialize)]
+struct DuplicatesQuery {
+    #[serde(default = "default_duplicate_threshold")]
+    threshold: f64,
+}
+
+fn default_duplicate_threshold() -> f64 {
+    0.8
+}
+
+#[derive(Deserialize)]
+struct EntitiesQuery {
+    /// Entities of this key instead of all known ones
+    key: Option<String>,
+    kind: Option<EntityKind>,
+    #[serde(default = "default_entity_limit")]
+    limit: usize,
+}
+
+fn default_entity_limit() -> usize {
+    100
+}
+
+#[derive(Deserialize)]
+struct MentionsQuery {
+    entity: String,
+}
+
+#[derive(Deserialize)]
+struct WatchRequest {
     query: String,
+}
+
+#[derive(Deserialize)]
+struct WatchEventsQuery {
+    /// Only events of this watch; all watches otherwise
+    watch: Option<WatchId>,
+}
+
+#[derive(Deserialize)]
+struct SuggestQuery {
+    q: String,
     #[serde(default = "default_limit")]
     limit: usize,
 }
@@ -77,8 +211,12 @@ struct SearchResult {
     key: String,
     score: f64,
     preview: String,
+    snippet: Option<Snippet>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    explanation: Option<Explanation>,
     #[serde(rename = "type")]
     result_type: String,
+    tier: Tier,
 }
 
 #[derive(Serialize)]
@@ -86,7 +224,33 @@ struct SearchResponse {
     query: String,
     matches: Vec<SearchResult>,
     search_time: f64,
+    /// Matches across all pages
     total_matches: usize,
+    next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    facets: Option<Facets>,
+}
+
+#[derive(Deserialize)]
+struct BeginEpisodeRequest {
+    participant: String,
+}
+
+#[derive(Serialize)]
+struct BeginEpisodeResponse {
+    id: EpisodeId,
+    participant: String,
+}
+
+#[derive(Deserialize)]
+struct AppendEventRequest {
+    kind: String,
+    key: Option<String>,
+    #[serde(default)]
+    payload: serde_json::Value,
+    /// Also write `payload` under `key` into working memory
+    #[serde(default)]
+    store: bool,
 }
 
 #[derive(Serialize)]
@@ -111,9 +275,95 @@ struct BenchmarkResults {
     tests: Vec<BenchmarkResult>,
 }
 
+/// Remote embedder configured by `BRAINMEMORY_EMBEDDING_URL` and the
+/// optional `BRAINMEMORY_EMBEDDING_MODEL` and `BRAINMEMORY_EMBEDDING_DIMENSIONS`
+fn remote_embedder_from_env() -> Option<RemoteEmbedder> {
+    let url = std::env::var("BRAINMEMORY_EMBEDDING_URL").ok()?;
+    let mut config = RemoteEmbedderConfig {
+        url,
+        model: std::env::var("BRAINMEMORY_EMBEDDING_MODEL").ok(),
+        ..Default::default()
+    };
+    if let Some(dimensions) = std::env::var("BRAINMEMORY_EMBEDDING_DIMENSIONS").ok().and_then(|d| d.parse().ok()) {
+        config.dimensions = dimensions;
+    }
+
+    match RemoteEmbedder::new(config) {
+        Ok(embedder) => {
+            tracing::info!("Using remote embeddings from {}", embedder.config().url);
+            Some(embedder)
+        }
+        Err(e) => {
+            tracing::error!("Ignoring BRAINMEMORY_EMBEDDING_URL: {}", e);
+            None
+        }
+    }
+}
+
+/// Box a group of routes behind a single reply type
+fn group<F, R>(routes: F) -> BoxedFilter<(Response,)>
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    routes.map(|reply: R| reply.into_response()).boxed()
+}
+
+/// Run a handler on the blocking thread pool. Handlers that may call a
+/// remote embedder use this so the async workers keep serving.
+async fn blocking<R: Reply + Send + 'static>(handler: impl FnOnce() -> R + Send + 'static) -> Result<R, Rejection> {
+    tokio::task::spawn_blocking(handler).await.map_err(|_| warp::reject())
+}
+
+/// Embed `text` without holding the memory lock; remote embedders wait on
+/// the network. `None` without an embedder.
+fn embed_unlocked(state: &ServerState, text: &str) -> Option<Precomputed> {
+    let embedder = state.brain.lock().ok()?.embedder()?;
+    Some(Precomputed::new(embedder, text))
+}
+
+/// `optimize_memory` with consolidation and embedding of the new facts
+/// done outside the lock
+fn optimize_unlocked(state: &ServerState) {
+    let (work, embedder) = match state.brain.lock() {
+        Ok(mut brain) => {
+            brain.move_idle_entries();
+            (brain.episodes_to_consolidate(), brain.embedder())
+        }
+        Err(_) => return,
+    };
+    let Some((consolidator, episodes)) = work else {
+        return;
+    };
+
+    let facts = consolidator.consolidate(&episodes.iter().collect::<Vec<_>>());
+    let embedded: Vec<Option<Precomputed>> = match &embedder {
+        Some(embedder) => {
+            let texts = facts.iter().map(|f| document_text(&f.key, &f.value)).collect();
+            Precomputed::batch(embedder, texts, REEMBED_BATCH).into_iter().map(Some).collect()
+        }
+        None => vec![None; facts.len()],
+    };
+    let ids: Vec<EpisodeId> = episodes.iter().map(|e| e.id).collect();
+    if let Ok(mut brain) = state.brain.lock() {
+        brain.write_facts(&ids, facts.into_iter().zip(embedded).collect());
+    }
+}
+
 pub async fn start_server(port: u16) {
+    // Re-embedding may call a remote embedder, keep it off the async workers
+    let brain = tokio::task::spawn_blocking(|| {
+        let mut brain = BrainMemory::new();
+        if let Some(embedder) = remote_embedder_from_env() {
+            brain.set_embedder(Some(Arc::new(embedder) as Arc<dyn Embedder>));
+        }
+        brain
+    })
+    .await
+    .expect("memory setup does not panic");
+
     let state = ServerState {
-        brain: Arc::new(Mutex::new(BrainMemory::new())),
+        brain: Arc::new(Mutex::new(brain)),
         start_time: Instant::now(),
     };
 
@@ -125,8 +375,9 @@ pub async fn start_server(port: u16) {
         let mut interval = interval(Duration::from_secs(60));
         loop {
             interval.tick().await;
-            if let Ok(mut brain) = state_clone.brain.lock() {
-                brain.optimize_memory();
+            let state = state_clone.clone();
+            if tokio::task::spawn_blocking(move || optimize_unlocked(&state)).await.is_err() {
+                tracing::error!("memory optimization panicked");
             }
         }
     });
@@ -160,9 +411,10 @@ pub async fn start_server(port: u16) {
     // POST /store
     let store = warp::path("store")
         .and(warp::post())
+        .and(warp::body::content_length_limit(MAX_STORE_BODY_BYTES))
         .and(warp::body::json())
         .and(state_filter.clone())
-        .map(handle_store);
+        .and_then(|req, state| blocking(move || handle_store(req, state)));
 
     // GET /retrieve/:key
     let retrieve = warp::path("retrieve")
@@ -171,12 +423,208 @@ pub async fn start_server(port: u16) {
         .and(state_filter.clone())
         .map(handle_retrieve);
 
+    // POST /patch
+    let patch = warp::path("patch")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(MAX_STORE_BODY_BYTES))
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(|req, state| blocking(move || handle_patch(req, state)));
+
+    // DELETE /forget/:key
+    let forget = warp::path("forget")
+        .and(warp::path::param())
+        .and(warp::delete())
+        .and(state_filter.clone())
+        .map(handle_forget);
+
+    // GET /keys?glob=|regex=
+    let keys = warp::path("keys")
+        .and(warp::get())
+        .and(warp::query::<PatternParams>())
+        .and(state_filter.clone())
+        .map(handle_keys);
+
+    // POST /forget {"glob"|"regex": ...}
+    let forget_matching = warp::path("forget")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_forget_matching);
+
+    // GET /export?glob=|regex=
+    let export = warp::path("export")
+        .and(warp::get())
+        .and(warp::query::<PatternParams>())
+        .and(state_filter.clone())
+        .map(handle_export);
+
+    // GET /duplicates?threshold=
+    let duplicates = warp::path("duplicates")
+        .and(warp::get())
+        .and(warp::query::<DuplicatesQuery>())
+        .and(state_filter.clone())
+        .map(handle_duplicates);
+
+    // GET /entities?key=&kind=&limit=
+    let list_entities = warp::path("entities")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<EntitiesQuery>())
+        .and(state_filter.clone())
+        .map(handle_list_entities);
+
+    // GET /entities/mentions?entity=
+    let mentions = warp::path!("entities" / "mentions")
+        .and(warp::get())
+        .and(warp::query::<MentionsQuery>())
+        .and(state_filter.clone())
+        .map(handle_mentions);
+
     // POST /search
     let search = warp::path("search")
         .and(warp::post())
         .and(warp::body::json())
         .and(state_filter.clone())
-        .map(handle_search);
+        .and_then(|req, state| blocking(move || handle_search(req, state)));
+
+    // POST /context
+    let context = warp::path("context")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .and_then(|req, state| blocking(move || handle_context(req, state)));
+
+    // GET /suggest?q=&limit=
+    let suggest = warp::path("suggest")
+        .and(warp::get())
+        .and(warp::query::<SuggestQuery>())
+        .and(state_filter.clone())
+        .map(handle_suggest);
+
+    // POST /schemas
+    let register_schema = warp::path("schemas")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_register_schema);
+
+    // GET /schemas
+    let list_schemas = warp::path("schemas")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .map(handle_list_schemas);
+
+    // DELETE /schemas/:prefix (the rest of the path, slashes included)
+    let unregister_schema = warp::path("schemas")
+        .and(warp::path::tail())
+        .and(warp::delete())
+        .and(state_filter.clone())
+        .map(handle_unregister_schema);
+
+    // POST /analyzer
+    let set_analyzer = warp::path("analyzer")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_set_analyzer);
+
+    // GET /analyzer
+    let get_analyzer = warp::path("analyzer")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .map(handle_get_analyzer);
+
+    // POST /autotag
+    let set_auto_tagging = warp::path("autotag")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_set_auto_tagging);
+
+    // POST /consolidator
+    let set_consolidator = warp::path("consolidator")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_set_consolidator);
+
+    // GET /autotag
+    let get_auto_tagging = warp::path("autotag")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .map(handle_get_auto_tagging);
+
+    // POST /episodes
+    let begin_episode = warp::path("episodes")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_begin_episode);
+
+    // GET /episodes?participant=&from=&to=
+    let query_episodes = warp::path("episodes")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<EpisodeQuery>())
+        .and(state_filter.clone())
+        .map(handle_query_episodes);
+
+    // POST /episodes/:id/events
+    let append_event = warp::path!("episodes" / EpisodeId / "events")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_append_event);
+
+    // POST /episodes/:id/close
+    let close_episode = warp::path!("episodes" / EpisodeId / "close")
+        .and(warp::post())
+        .and(state_filter.clone())
+        .map(handle_close_episode);
+
+    // GET /episodes/:id
+    let replay_episode = warp::path!("episodes" / EpisodeId)
+        .and(warp::get())
+        .and(state_filter.clone())
+        .map(handle_replay_episode);
+
+    // GET /episodes/:id/neustart
+    let episode_handoff = warp::path!("episodes" / EpisodeId / "neustart")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .map(handle_episode_handoff);
+
+    // POST /watches
+    let add_watch = warp::path("watches")
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(state_filter.clone())
+        .map(handle_add_watch);
+
+    // GET /watches
+    let list_watches = warp::path("watches")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(state_filter.clone())
+        .map(handle_list_watches);
+
+    // DELETE /watches/:id
+    let remove_watch = warp::path!("watches" / WatchId)
+        .and(warp::delete())
+        .and(state_filter.clone())
+        .map(handle_remove_watch);
+
+    // GET /watches/events?watch= (server-sent events)
+    let watch_events = warp::path!("watches" / "events")
+        .and(warp::get())
+        .and(warp::query::<WatchEventsQuery>())
+        .and(state_filter.clone())
+        .map(handle_watch_events);
 
     // POST /benchmark
     let benchmark = warp::path("benchmark")
@@ -184,13 +632,52 @@ pub async fn start_server(port: u16) {
         .and(state_filter.clone())
         .map(handle_benchmark);
 
-    let routes = status
-        .or(memory)
-        .or(performance)
-        .or(store)
-        .or(retrieve)
-        .or(search)
-        .or(benchmark)
+    // Routes are combined per group; each group is boxed so the filter
+    // type stays shallow as routes are added
+    let entries = group(
+        store
+            .or(retrieve)
+            .or(patch)
+            .or(forget)
+            .or(keys)
+            .or(forget_matching)
+            .or(export)
+            .or(duplicates),
+    );
+    let searching = group(search.or(context).or(suggest).or(list_entities).or(mentions));
+    let schemas = group(register_schema.or(list_schemas).or(unregister_schema));
+    let episodes = group(
+        begin_episode
+            .or(query_episodes)
+            .or(append_event)
+            .or(close_episode)
+            .or(replay_episode)
+            .or(episode_handoff),
+    );
+    let watches = group(add_watch.or(list_watches).or(remove_watch).or(watch_events));
+    let admin = group(
+        status
+            .or(memory)
+            .or(performance)
+            .or(set_analyzer)
+            .or(get_analyzer)
+            .or(set_auto_tagging)
+            .or(get_auto_tagging)
+            .or(set_consolidator)
+            .or(benchmark),
+    );
+
+    let routes = entries
+        .or(searching)
+        .unify()
+        .or(schemas)
+        .unify()
+        .or(episodes)
+        .unify()
+        .or(watches)
+        .unify()
+        .or(admin)
+        .unify()
         .with(cors);
 
     println!("🧠 BrainMemory Server starting on port {}", port);
@@ -228,7 +715,7 @@ fn handle_memory(state: ServerState) -> impl Reply {
     }
 }
 
-fn handle_performance(state: ServerState) -> impl Reply {
+fn handle_performance(_state: ServerState) -> impl Reply {
     let performance = serde_json::json!({
         "operations": {
             "store": {
@@ -264,11 +751,26 @@ fn handle_performance(state: ServerState) -> impl Reply {
     warp::reply::json(&performance)
 }
 
-fn handle_store(req: StoreRequest, state: ServerState) -> impl Reply {
-    let start = Instant::now();
-    
+fn handle_store(mut req: StoreRequest, state: ServerState) -> impl Reply {
+    req.options.embedding = embed_unlocked(&state, &document_text(&req.key, &req.value));
+
     if let Ok(mut brain) = state.brain.lock() {
-        brain.store(&req.key, req.value.clone());
+        let result = brain.store_with(&req.key, req.value.clone(), &req.options);
+        if let Err(e) = result {
+            let violations = match &e {
+                StoreError::SchemaViolation { violations, .. } => violations.clone(),
+                _ => Vec::new(),
+            };
+            return warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "stored": false,
+                    "key": req.key,
+                    "error": e.to_string(),
+                    "violations": violations,
+                })),
+                store_error_status(&e),
+            );
+        }
         
         let response = StoreResponse {
             stored: true,
@@ -277,10 +779,299 @@ fn handle_store(req: StoreRequest, state: ServerState) -> impl Reply {
             timestamp: chrono::Utc::now().to_rfc3339(),
         };
         
-        warp::reply::json(&response)
+        warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to store data"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_patch(req: PatchRequest, state: ServerState) -> impl Reply {
+    // Embed the patched value ahead; if the entry changes meanwhile it is
+    // embedded again under the lock
+    let patched = state.brain.lock().ok().and_then(|brain| brain.patched(&req.key, &req.patch).ok());
+    let options = StoreOptions {
+        embedding: patched.and_then(|value| embed_unlocked(&state, &document_text(&req.key, &value))),
+        ..Default::default()
+    };
+    let result = match state.brain.lock() {
+        Ok(mut brain) => brain.patch_with(&req.key, &req.patch, &options),
+        Err(_) => {
+            return warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "error": "Failed to patch data" })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+        }
+    };
+    match result {
+        Ok(()) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "patched": true, "key": req.key })),
+            StatusCode::OK,
+        ),
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "patched": false, "key": req.key, "error": e.to_string() })),
+            store_error_status(&e),
+        ),
+    }
+}
+
+fn handle_forget(key: String, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        let forgotten = brain.forget(&key);
+        warp::reply::json(&serde_json::json!({ "forgotten": forgotten, "key": key }))
     } else {
         warp::reply::json(&serde_json::json!({
-            "error": "Failed to store data"
+            "error": "Failed to forget data"
+        }))
+    }
+}
+
+fn store_error_status(e: &StoreError) -> StatusCode {
+    match e {
+        StoreError::InvalidKey { .. } => StatusCode::BAD_REQUEST,
+        StoreError::KeyNotFound(_) => StatusCode::NOT_FOUND,
+        StoreError::ValueTooLarge { .. }
+        | StoreError::TooDeeplyNested { .. }
+        | StoreError::QuotaExceeded { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        StoreError::SchemaViolation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        StoreError::Episode(EpisodeError::NotFound(_)) => StatusCode::NOT_FOUND,
+        StoreError::Episode(EpisodeError::AlreadyClosed(_)) => StatusCode::CONFLICT,
+        StoreError::NearDuplicate { .. } => StatusCode::CONFLICT,
+    }
+}
+
+#[derive(Deserialize)]
+struct RegisterSchemaRequest {
+    prefix: String,
+    schema: serde_json::Value,
+}
+
+fn handle_keys(params: PatternParams, state: ServerState) -> impl Reply {
+    let pattern = match params.compile() {
+        Ok(pattern) => pattern,
+        Err(e) => return pattern_error(e),
+    };
+    if let Ok(brain) = state.brain.lock() {
+        let keys = brain.keys(pattern.as_ref());
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "count": keys.len(), "keys": keys })),
+            StatusCode::OK,
+        )
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to list keys"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_forget_matching(params: PatternParams, state: ServerState) -> impl Reply {
+    let pattern = match params.compile() {
+        Ok(Some(pattern)) => pattern,
+        Ok(None) => return pattern_error(PatternError { message: "glob or regex required".to_string() }),
+        Err(e) => return pattern_error(e),
+    };
+    if let Ok(mut brain) = state.brain.lock() {
+        let forgotten = brain.forget_matching(&pattern);
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "count": forgotten.len(), "forgotten": forgotten })),
+            StatusCode::OK,
+        )
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to forget keys"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_export(params: PatternParams, state: ServerState) -> impl Reply {
+    let pattern = match params.compile() {
+        Ok(pattern) => pattern,
+        Err(e) => return pattern_error(e),
+    };
+    if let Ok(brain) = state.brain.lock() {
+        let entries = brain.export(pattern.as_ref());
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "count": entries.len(), "entries": entries })),
+            StatusCode::OK,
+        )
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to export"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_duplicates(query: DuplicatesQuery, state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        let clusters = brain.duplicates(query.threshold);
+        warp::reply::json(&serde_json::json!({
+            "threshold": query.threshold,
+            "clusters": clusters,
+        }))
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to find duplicates"
+        }))
+    }
+}
+
+fn handle_list_entities(query: EntitiesQuery, state: ServerState) -> impl Reply {
+    let Ok(brain) = state.brain.lock() else {
+        return warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to list entities"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    };
+    let Some(key) = query.key else {
+        return warp::reply::with_status(
+            warp::reply::json(&brain.list_entities(query.kind, query.limit)),
+            StatusCode::OK,
+        );
+    };
+    match brain.entities(&key) {
+        Some(entities) => {
+            let entities: Vec<_> = entities.into_iter().filter(|e| query.kind.is_none_or(|k| e.kind == k)).collect();
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "key": key,
+                    "entities": entities,
+                })),
+                StatusCode::OK,
+            )
+        }
+        None => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": StoreError::KeyNotFound(key).to_string()
+            })),
+            StatusCode::NOT_FOUND,
+        ),
+    }
+}
+
+fn handle_mentions(query: MentionsQuery, state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        let entries = brain.mentioning(&query.entity);
+        warp::reply::json(&serde_json::json!({
+            "entity": query.entity,
+            "total": entries.len(),
+            "entries": entries,
+        }))
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to find mentions"
+        }))
+    }
+}
+
+fn handle_register_schema(req: RegisterSchemaRequest, state: ServerState) -> impl Reply {
+    let result = match state.brain.lock() {
+        Ok(mut brain) => brain.register_schema(&req.prefix, req.schema),
+        Err(_) => Err("Failed to access memory".to_string()),
+    };
+    match result {
+        Ok(()) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "registered": true, "prefix": req.prefix })),
+            StatusCode::OK,
+        ),
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "registered": false, "error": e })),
+            StatusCode::BAD_REQUEST,
+        ),
+    }
+}
+
+fn handle_unregister_schema(prefix: warp::path::Tail, state: ServerState) -> impl Reply {
+    let prefix = prefix.as_str();
+    match state.brain.lock() {
+        Ok(mut brain) => {
+            let removed = brain.unregister_schema(prefix).is_some();
+            let status = if removed { StatusCode::OK } else { StatusCode::NOT_FOUND };
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "unregistered": removed, "prefix": prefix })),
+                status,
+            )
+        }
+        Err(_) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": "Failed to access memory" })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    }
+}
+
+fn handle_list_schemas(state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        warp::reply::json(brain.schemas().schemas())
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to list schemas"
+        }))
+    }
+}
+
+fn handle_set_analyzer(config: AnalyzerConfig, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        brain.set_analyzer(config);
+        warp::reply::json(brain.analyzer_config())
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to set analyzer"
+        }))
+    }
+}
+
+fn handle_get_analyzer(state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        warp::reply::json(brain.analyzer_config())
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to get analyzer"
+        }))
+    }
+}
+
+fn handle_set_auto_tagging(config: AutoTagConfig, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        brain.set_auto_tagging(config);
+        warp::reply::json(brain.auto_tag_config())
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to set auto-tagging"
+        }))
+    }
+}
+
+fn handle_set_consolidator(config: ConsolidatorConfig, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        brain.set_consolidator(config.build());
+        warp::reply::json(&config)
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to set consolidator"
+        }))
+    }
+}
+
+fn handle_get_auto_tagging(state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        warp::reply::json(brain.auto_tag_config())
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to get auto-tagging"
         }))
     }
 }
@@ -288,7 +1079,7 @@ fn handle_store(req: StoreRequest, state: ServerState) -> impl Reply {
 fn handle_retrieve(key: String, state: ServerState) -> impl Reply {
     let start = Instant::now();
     
-    if let Ok(brain) = state.brain.lock() {
+    if let Ok(mut brain) = state.brain.lock() {
         let value = brain.retrieve(&key);
         let retrieval_time = start.elapsed().as_secs_f64() * 1000.0;
         
@@ -310,36 +1101,328 @@ fn handle_retrieve(key: String, state: ServerState) -> impl Reply {
 
 fn handle_search(req: SearchRequest, state: ServerState) -> impl Reply {
     let start = Instant::now();
-    
-    if let Ok(brain) = state.brain.lock() {
-        let results = brain.search(&req.query, req.limit);
+    let parsed = req.q.as_deref().map(crate::query::parse);
+    let mut explain_query = req.query.clone();
+    if let Some(Ok(parsed)) = &parsed {
+        for term in parsed.query.positive_terms() {
+            explain_query.push(' ');
+            explain_query.push_str(&term);
+        }
+    }
+    let query_embedding = (req.mode != SearchMode::Keyword).then(|| embed_unlocked(&state, &req.query)).flatten();
+    let explain_embedding = req.explain.then(|| embed_unlocked(&state, &explain_query)).flatten();
+
+    if let Ok(mut brain) = state.brain.lock() {
+        let mut options = SearchOptions {
+            limit: req.limit,
+            mode: req.mode,
+            fusion: req.fusion.unwrap_or_default(),
+            tier: req.tier,
+            bm25: req.bm25.unwrap_or_default(),
+            fuzzy: req.fuzzy,
+            semantic: req.semantic.unwrap_or_default(),
+            ranking: req.ranking.unwrap_or_default(),
+            facets: req.facets,
+            keys: match req.keys.compile() {
+                Ok(keys) => keys,
+                Err(e) => return pattern_error(e),
+            },
+            query_embedding,
+        };
+        let mut terms = brain.match_terms(&req.query, &options);
+        let cursor = req.cursor.as_deref();
+        let page = match parsed {
+            None => brain.search_page(&req.query, &options, cursor),
+            Some(Ok(parsed)) => {
+                terms.extend(parsed.query.positive_terms());
+                brain.search_query_page(&req.query, &parsed, &options, cursor)
+            }
+            Some(Err(e)) => {
+                return warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({
+                        "error": format!("invalid query: {}", e),
+                        "position": e.position,
+                    })),
+                    StatusCode::BAD_REQUEST,
+                );
+            }
+        };
+        let page = match page {
+            Ok(page) => page,
+            Err(e) => {
+                let status = match e {
+                    CursorError::Invalid => StatusCode::BAD_REQUEST,
+                    CursorError::Expired => StatusCode::GONE,
+                };
+                return warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+                    status,
+                );
+            }
+        };
+        let results = page.hits;
         let search_time = start.elapsed().as_secs_f64() * 1000.0;
         
-        let matches: Vec<SearchResult> = results.into_iter().map(|(key, score)| {
+        let explanations: Vec<Option<Explanation>> = if req.explain {
+            options.query_embedding = explain_embedding;
+            brain.explain(&results, &explain_query, &options).into_iter().map(Some).collect()
+        } else {
+            vec![None; results.len()]
+        };
+        let matches: Vec<SearchResult> = results.into_iter().zip(explanations).map(|(hit, explanation)| {
+            let snippet = brain.snippet(&hit.key, &terms, &req.snippet);
             SearchResult {
-                key: key.clone(),
-                score,
-                preview: format!("Preview for {}", key),
+                explanation,
+                preview: snippet.as_ref().map(|s| s.text.clone()).unwrap_or_default(),
+                snippet,
+                key: hit.key,
+                score: hit.score,
                 result_type: "general".to_string(),
+                tier: hit.tier,
             }
         }).collect();
         
         let response = SearchResponse {
             query: req.query,
-            total_matches: matches.len(),
+            total_matches: page.total,
+            next_cursor: page.next_cursor,
+            facets: page.facets,
             matches,
             search_time,
         };
         
-        warp::reply::json(&response)
+        warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to search"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_context(mut req: ContextRequest, state: ServerState) -> impl Reply {
+    if req.options.mode != SearchMode::Keyword {
+        req.options.task_embedding = embed_unlocked(&state, &req.task);
+    }
+    if let Ok(brain) = state.brain.lock() {
+        let pack = brain.recall_context(&req.task, &req.options);
+        warp::reply::with_status(warp::reply::json(&pack), StatusCode::OK)
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to assemble context"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_suggest(query: SuggestQuery, state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        let suggestions = brain.suggest(&query.q, query.limit);
+        warp::reply::json(&serde_json::json!({
+            "query": query.q,
+            "suggestions": suggestions,
+        }))
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to suggest"
+        }))
+    }
+}
+
+fn handle_add_watch(req: WatchRequest, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        match brain.watch(&req.query) {
+            Ok(id) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "id": id, "query": req.query })),
+                StatusCode::CREATED,
+            ),
+            Err(e) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "error": format!("invalid query: {}", e),
+                    "position": e.position,
+                })),
+                StatusCode::BAD_REQUEST,
+            ),
+        }
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to add watch"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_list_watches(state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        let watches: Vec<_> = brain.watches().collect();
+        warp::reply::json(&serde_json::json!({ "watches": watches }))
     } else {
         warp::reply::json(&serde_json::json!({
-            "error": "Failed to search"
+            "error": "Failed to list watches"
         }))
     }
 }
 
-fn handle_benchmark(state: ServerState) -> impl Reply {
+fn handle_remove_watch(id: WatchId, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        let removed = brain.unwatch(id);
+        warp::reply::json(&serde_json::json!({ "removed": removed, "id": id }))
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to remove watch"
+        }))
+    }
+}
+
+fn handle_watch_events(query: WatchEventsQuery, state: ServerState) -> warp::reply::Response {
+    let receiver = match state.brain.lock() {
+        Ok(brain) => brain.subscribe(),
+        Err(_) => {
+            return warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "error": "Failed to subscribe"
+                })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .into_response();
+        }
+    };
+
+    let events = futures_util::stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if query.watch.is_none_or(|id| id == event.watch) => {
+                    let sse = warp::sse::Event::default().event("match").json_data(&event);
+                    return Some((sse, receiver));
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    let sse = Ok::<_, serde_json::Error>(warp::sse::Event::default().event("lagged").data(missed.to_string()));
+                    return Some((sse, receiver));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    warp::sse::reply(warp::sse::keep_alive().stream(events)).into_response()
+}
+
+fn handle_begin_episode(req: BeginEpisodeRequest, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        let id = brain.begin_episode(&req.participant);
+        warp::reply::json(&BeginEpisodeResponse {
+            id,
+            participant: req.participant,
+        })
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to begin episode"
+        }))
+    }
+}
+
+fn handle_query_episodes(query: EpisodeQuery, state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        let episodes = brain.query_episodes(&query);
+        warp::reply::json(&episodes)
+    } else {
+        warp::reply::json(&serde_json::json!({
+            "error": "Failed to query episodes"
+        }))
+    }
+}
+
+fn handle_append_event(id: EpisodeId, req: AppendEventRequest, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        let result = match (&req.key, req.store) {
+            (Some(key), true) => brain.store_in_episode(id, key, req.payload),
+            _ => brain
+                .append_event(id, &req.kind, req.key.as_deref(), req.payload)
+                .map(|_| ())
+                .map_err(StoreError::from),
+        };
+        match result {
+            Ok(()) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "appended": true, "episode": id })),
+                StatusCode::OK,
+            ),
+            Err(e) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+                store_error_status(&e),
+            ),
+        }
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to append event"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_close_episode(id: EpisodeId, state: ServerState) -> impl Reply {
+    if let Ok(mut brain) = state.brain.lock() {
+        match brain.close_episode(id).map_err(StoreError::from) {
+            Ok(()) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "closed": true, "episode": id })),
+                StatusCode::OK,
+            ),
+            Err(e) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+                store_error_status(&e),
+            ),
+        }
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to close episode"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_replay_episode(id: EpisodeId, state: ServerState) -> impl Reply {
+    if let Ok(brain) = state.brain.lock() {
+        match brain.episode(id) {
+            Some(episode) => warp::reply::with_status(warp::reply::json(episode), StatusCode::OK),
+            None => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "error": EpisodeError::NotFound(id).to_string() })),
+                StatusCode::NOT_FOUND,
+            ),
+        }
+    } else {
+        warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "error": "Failed to replay episode"
+            })),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    }
+}
+
+fn handle_episode_handoff(id: EpisodeId, state: ServerState) -> impl Reply {
+    let markdown = match state.brain.lock() {
+        Ok(brain) => brain.episode(id).map(|e| e.to_neustart_markdown()),
+        Err(_) => None,
+    };
+    match markdown {
+        Some(markdown) => warp::reply::with_status(markdown, StatusCode::OK),
+        None => warp::reply::with_status(
+            format!("episode {} not found", id),
+            StatusCode::NOT_FOUND,
+        ),
+    }
+}
+
+fn handle_benchmark(_state: ServerState) -> impl Reply {
     // Simulate benchmark execution
     let results = BenchmarkResults {
         shell_total: 567.8,
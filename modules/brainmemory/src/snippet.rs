@@ -0,0 +1,189 @@
+// SimplyKI BrainMemory - Result Snippets
+// Erstellt: 2026-10-19 17:55:00 CEST
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::text::token_spans;
+
+/// Character range `[start, end)` in the source text
+type Span = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetOptions {
+    /// Characters of the field shown, not counting ellipses
+    pub max_chars: usize,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self { max_chars: 160 }
+    }
+}
+
+/// Character range of a matched term within `Snippet::text`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snippet {
+    /// JSON pointer of the field shown; empty for the whole value
+    pub path: String,
+    pub text: String,
+    pub highlights: Vec<Highlight>,
+}
+
+/// Excerpt of the string field with the most distinct tokens accepted by
+/// `is_match`, centered on its first match. Values without matching
+/// strings show their first string field, or compact JSON if there is none.
+pub fn snippet(value: &Value, is_match: &dyn Fn(&str) -> bool, options: &SnippetOptions) -> Snippet {
+    let mut leaves = Vec::new();
+    string_leaves(value, String::new(), &mut leaves);
+
+    // (distinct terms, leaf, match spans)
+    let mut best: Option<(usize, usize, Vec<Span>)> = None;
+    for (leaf, (_, text)) in leaves.iter().enumerate() {
+        let matches: Vec<(usize, usize, String)> = token_spans(text)
+            .into_iter()
+            .filter(|(_, _, token)| is_match(token))
+            .collect();
+        let mut distinct: Vec<&String> = matches.iter().map(|(_, _, t)| t).collect();
+        distinct.sort();
+        distinct.dedup();
+
+        if best.as_ref().is_none_or(|(count, ..)| distinct.len() > *count) {
+            let spans = matches.iter().map(|(s, e, _)| (*s, *e)).collect();
+            best = Some((distinct.len(), leaf, spans));
+        }
+    }
+
+    match best {
+        Some((_, leaf, spans)) => {
+            let (path, text) = leaves.swap_remove(leaf);
+            excerpt(path, text, &spans, options.max_chars)
+        }
+        None => excerpt(String::new(), &value.to_string(), &[], options.max_chars),
+    }
+}
+
+/// JSON pointers of string fields with a token accepted by `is_match`
+pub fn matching_fields(value: &Value, is_match: &dyn Fn(&str) -> bool) -> Vec<String> {
+    let mut leaves = Vec::new();
+    string_leaves(value, String::new(), &mut leaves);
+    leaves
+        .into_iter()
+        .filter(|(_, text)| token_spans(text).iter().any(|(_, _, token)| is_match(token)))
+        .map(|(path, _)| path)
+        .collect()
+}
+
+fn string_leaves<'a>(value: &'a Value, path: String, out: &mut Vec<(String, &'a str)>) {
+    match value {
+        Value::String(s) => out.push((path, s)),
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                string_leaves(item, format!("{}/{}", path, i), out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                string_leaves(item, format!("{}/{}", path, escaped), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Cut `text` to `max_chars` around the first span and shift the spans
+fn excerpt(path: String, text: &str, spans: &[Span], max_chars: usize) -> Snippet {
+    let chars: Vec<char> = text.chars().collect();
+    let max_chars = max_chars.max(1);
+
+    let (mut start, mut end) = (0, chars.len().min(max_chars));
+    if chars.len() > max_chars {
+        if let Some(&(first, _)) = spans.first() {
+            // Some context before the match, the rest after it
+            start = first.saturating_sub(max_chars / 4).min(chars.len() - max_chars);
+            end = start + max_chars;
+        }
+        // Avoid cutting words in half where possible
+        if start > 0 {
+            if let Some(space) = chars[start..end].iter().position(|c| c.is_whitespace()) {
+                if space < max_chars / 4 && spans.first().is_none_or(|&(s, _)| start + space < s) {
+                    start += space + 1;
+                }
+            }
+        }
+        if end < chars.len() {
+            if let Some(space) = chars[start..end].iter().rposition(|c| c.is_whitespace()) {
+                if start + space > end - max_chars / 4 {
+                    end = start + space;
+                }
+            }
+        }
+    }
+
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    let offset = prefix.chars().count();
+    let highlights = spans
+        .iter()
+        .filter(|&&(s, e)| s >= start && e <= end)
+        .map(|&(s, e)| Highlight { start: s - start + offset, end: e - start + offset })
+        .collect();
+
+    Snippet {
+        path,
+        text: format!("{}{}{}", prefix, chars[start..end].iter().collect::<String>(), suffix),
+        highlights,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |token| words.contains(&token)
+    }
+
+    #[test]
+    fn test_snippet_picks_best_field() {
+        let value = serde_json::json!({
+            "title": "Speicher",
+            "notes": ["nothing here", "Der Arbeitsspeicher ist voll, Speicher prüfen"],
+            "size/bytes": 42
+        });
+        let snippet = snippet(&value, &terms(&["speicher", "voll"]), &SnippetOptions::default());
+        assert_eq!(snippet.path, "/notes/1");
+        let highlighted: Vec<String> = snippet
+            .highlights
+            .iter()
+            .map(|h| snippet.text.chars().skip(h.start).take(h.end - h.start).collect())
+            .collect();
+        assert_eq!(highlighted, vec!["voll", "Speicher"]);
+
+        let fallback = super::snippet(&serde_json::json!({"n": 1}), &terms(&["x"]), &SnippetOptions::default());
+        assert_eq!(fallback.text, "{\"n\":1}");
+        assert!(fallback.highlights.is_empty());
+    }
+
+    #[test]
+    fn test_long_text_is_cut_around_match() {
+        let text = format!("{} needle {}", "lorem ipsum ".repeat(20), "dolor sit ".repeat(20));
+        let value = Value::String(text);
+        let snippet = snippet(&value, &terms(&["needle"]), &SnippetOptions { max_chars: 40 });
+
+        assert_eq!(snippet.path, "");
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+        assert!(snippet.text.chars().count() <= 42);
+        let h = snippet.highlights[0];
+        let matched: String = snippet.text.chars().skip(h.start).take(h.end - h.start).collect();
+        assert_eq!(matched, "needle");
+    }
+}
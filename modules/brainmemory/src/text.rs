@@ -0,0 +1,84 @@
+// SimplyKI BrainMemory - Text Helpers
+// Erstellt: 2026-10-19 10:05:00 CEST
+
+use serde_json::Value;
+
+/// Collect all string leaves of a JSON value (object keys excluded)
+pub fn collect_strings<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => out.push(s),
+        Value::Array(items) => {
+            for item in items {
+                collect_strings(item, out);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values() {
+                collect_strings(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lowercased alphanumeric tokens; `_`, `-` and `.` also split words
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+/// Tokens of `text` with their character ranges, same rules as `tokenize`
+pub fn token_spans(text: &str) -> Vec<(usize, usize, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_alphanumeric() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && chars[i].is_alphanumeric() {
+            i += 1;
+        }
+        spans.push((start, i, chars[start..i].iter().collect::<String>().to_lowercase()));
+    }
+    spans
+}
+
+/// FNV-1a, stable across builds and platforms
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Text a document is searched by: its key followed by its string content
+pub fn document_text(key: &str, value: &Value) -> String {
+    let mut strings = vec![key];
+    collect_strings(value, &mut strings);
+    strings.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_and_tokenize() {
+        let value = serde_json::json!({"a": "Hello World", "b": [1, "server.rs"], "c": {"d": "Größe"}});
+        let mut strings = Vec::new();
+        collect_strings(&value, &mut strings);
+        strings.sort();
+        assert_eq!(strings, vec!["Größe", "Hello World", "server.rs"]);
+        assert_eq!(tokenize("Hello, server.rs_Größe"), vec!["hello", "server", "rs", "größe"]);
+
+        let spans = token_spans("Größe: 42kB");
+        assert_eq!(spans, vec![(0, 5, "größe".to_string()), (7, 11, "42kb".to_string())]);
+    }
+}
@@ -0,0 +1,129 @@
+// SimplyKI BrainMemory - Standing Queries
+// Erstellt: 2026-10-19 20:20:00 CEST
+
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::query::{self, Document, Query, QueryError};
+use crate::search::Tier;
+
+pub type WatchId = u64;
+
+/// Events buffered per subscriber before the slowest one starts lagging
+const EVENT_CAPACITY: usize = 256;
+
+/// A registered structured query, see `crate::query`
+#[derive(Debug, Clone, Serialize)]
+pub struct Watch {
+    pub id: WatchId,
+    pub query: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip)]
+    parsed: Query,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /// The key did not exist before
+    Stored,
+    /// New value or new tags for an existing key
+    Updated,
+}
+
+/// An entry matched a watch after a write
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WatchEvent {
+    pub watch: WatchId,
+    pub key: String,
+    pub change: Change,
+    pub tier: Tier,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchRegistry {
+    watches: BTreeMap<WatchId, Watch>,
+    next_id: WatchId,
+    events: broadcast::Sender<WatchEvent>,
+}
+
+impl Default for WatchRegistry {
+    fn default() -> Self {
+        Self {
+            watches: BTreeMap::new(),
+            next_id: 0,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl WatchRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a query; sort clauses are accepted but have no effect
+    pub fn add(&mut self, text: &str) -> Result<WatchId, QueryError> {
+        let parsed = query::parse(text)?;
+        self.next_id += 1;
+        let id = self.next_id;
+        self.watches.insert(id, Watch {
+            id,
+            query: text.to_string(),
+            created_at: Utc::now(),
+            parsed: parsed.query,
+        });
+        Ok(id)
+    }
+
+    pub fn remove(&mut self, id: WatchId) -> bool {
+        self.watches.remove(&id).is_some()
+    }
+
+    pub fn list(&self) -> impl Iterator<Item = &Watch> {
+        self.watches.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+
+    /// Events of all watches from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish an event for every watch matching the written entry
+    pub fn notify(&self, doc: &impl Document, change: Change) {
+        for watch in self.watches.values().filter(|w| w.parsed.matches(doc)) {
+            // Fails only without subscribers, which is fine
+            let _ = self.events.send(WatchEvent {
+                watch: watch.id,
+                key: doc.key().to_string(),
+                change,
+                tier: doc.tier(),
+                at: Utc::now(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut watches = WatchRegistry::new();
+        let id = watches.add("tag:bug AND ns:simplyki").unwrap();
+        assert_eq!(watches.list().map(|w| w.id).collect::<Vec<_>>(), vec![id]);
+        assert_eq!(watches.add("tag:").unwrap_err().position, 4);
+
+        assert!(watches.remove(id));
+        assert!(!watches.remove(id));
+        assert!(watches.is_empty());
+    }
+}